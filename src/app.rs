@@ -1,24 +1,52 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-use anyhow::anyhow;
-
+use chrono::{DateTime, Local};
 use colored::Colorize;
 #[cfg(not(test))]
-use dialoguer::{theme::ColorfulTheme, Confirm, FuzzySelect};
+use dialoguer::{theme::ColorfulTheme, FuzzySelect};
 
-use crate::{enums::ConfigType, prelude::*};
+use crate::attachments::{self, VaultFiles};
+use crate::doctor::{self, Check};
+use crate::export;
+use crate::obsidian::Obsidian;
+use crate::revisions::{diff_lines, NoteRevisions};
+use crate::{enums::ConfigType, enums::LinkStyle, git, prelude::*, shell::Shell, tui::Tui};
 
 lazy_static! {
     // Mutex is used to allow for mutable access of global state.
     // CONFIG should remain the ONLY mutable global struct.
-    pub static ref CONFIG: Mutex<Config> = Mutex::new(Config::load());
+    pub static ref CONFIG: Mutex<Config> = Mutex::new(Config::default());
 }
 
 pub struct App {
     vaults: Vaults,
     editor: Editor,
     templates: Folder,
+    prompts: Prompts,
+    /// the command line being run, for the history log
+    command_line: Option<String>,
+    /// changes made by the current command
+    changes: Vec<Change>,
+    /// number of changes that have already been committed to git
+    committed: usize,
+}
+
+/// Where the folders and notes named by a command are looked for. Commands
+/// look for folders from the root of the vault, the TUI from the folder that
+/// it shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Vault,
+    ActiveFolder,
+}
+
+/// Files changed by a command, for the history log and git commits.
+#[derive(Clone)]
+struct Change {
+    /// e.g. `rename note a -> b`
+    description: String,
+    paths: Vec<PathBuf>,
 }
 
 impl App {
@@ -30,14 +58,57 @@ impl App {
     ) -> JeffResult<Message> {
         if let (Some(name), Some(location)) = (name, location) {
             self.vaults.create_vault(name, location)?;
+            let vault_path = self.vaults.get_vault_path(name)?;
+            self.record(OperationKind::Create, ItemType::Vl, None, vault_path)?;
             Ok(Message::ItemCreated(ItemType::Vl, name.to_owned()))
         } else if name.is_some() && show_loc {
             let name = name.clone().unwrap();
-            self.vaults.show_vault_location(name);
-            Ok(Message::Empty)
+            let vaults = self
+                .vaults
+                .list_vaults()
+                .into_iter()
+                .filter(|vault| vault.name == name)
+                .collect();
+            Ok(Message::Vaults(vaults, true))
         } else {
-            self.vaults.list_vaults(show_loc);
-            Ok(Message::Empty)
+            Ok(Message::Vaults(self.vaults.list_vaults(), show_loc))
+        }
+    }
+
+    pub fn vault_action(&mut self, action: &VaultAction) -> JeffResult<Message> {
+        match action {
+            VaultAction::Import { name, path } => {
+                self.vaults.import_vault(name, path)?;
+                let vault_path = self.vaults.get_vault_path(name)?;
+                self.changed(format!("import vault {}", name), vec![vault_path]);
+
+                Ok(Message::VaultImported(name.to_owned()))
+            }
+            VaultAction::Relink { name, path } => {
+                let path = self.vaults.relink_vault(name, path)?;
+                self.changed(format!("relink vault {}", name), vec![path.clone()]);
+
+                Ok(Message::VaultRelinked(name.to_owned(), path))
+            }
+            VaultAction::Archive { name, archive } => {
+                let archive = self.vaults.archive_vault(name, archive)?;
+
+                Ok(Message::VaultArchived(name.to_owned(), archive))
+            }
+            VaultAction::Restore {
+                archive,
+                name,
+                location,
+            } => {
+                let (name, path) = self.vaults.restore_vault_archive(
+                    archive,
+                    name.as_deref(),
+                    location.as_deref(),
+                )?;
+                self.changed(format!("restore vault {}", name), vec![path.clone()]);
+
+                Ok(Message::VaultUnarchived(name, path))
+            }
         }
     }
 
@@ -48,93 +119,157 @@ impl App {
 
     pub fn create_note(
         &mut self,
-        name: &String,
+        name: &str,
         from_template: bool,
         template_name: &Option<String>,
+        extension: &Option<String>,
     ) -> JeffResult<Message> {
         let vault = self.vaults.ref_current()?;
-        let maybe_note = vault.get_note_with_name(name);
+        let (name, name_extension) = Note::split_extension(name);
+        let name = name.to_string();
+        let extension = extension
+            .as_deref()
+            .or(name_extension)
+            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+            .unwrap_or_else(|| vault.default_note_extension());
+        if !vault.note_extensions().contains(&extension) {
+            return Err(Error::UnrecognisedExtension(extension));
+        }
+
+        let maybe_note = vault.get_note_with_name(&format!("{}.{}", name, extension));
         let templates = self.templates.notes();
+        let obsidian = Obsidian::load(vault.get_location())?;
 
         if from_template && template_name.is_none() {
-            return Err(anyhow!("Must specify template name"));
+            return Err(Error::TemplateNameRequired);
         }
 
         if let Ok(note) = maybe_note {
-            return Err(anyhow!(
-                "Note with name [{}] already exists",
-                note.get_name()
-            ));
+            return Err(Error::ItemAlreadyExists(ItemType::Nt, note.get_name()));
         }
 
-        let note_path = Note::generate_abs_path(&vault.get_active_location(), name);
+        let note_folder = match obsidian.as_ref().and_then(Obsidian::new_note_folder) {
+            Some(folder) => {
+                std::fs::create_dir_all(&folder)?;
+                folder
+            }
+            None => vault.get_active_location().to_path_buf(),
+        };
+        let note_path = Note::generate_abs_path_with_extension(&note_folder, &name, &extension);
+
+        if note_path.exists() {
+            return Err(Error::ItemAlreadyExists(ItemType::Nt, name.to_owned()));
+        }
 
         if from_template {
             let template_name = template_name.to_owned().unwrap();
-            let maybe_template = item_with_name::<Note>(&templates, &template_name);
-
-            if maybe_template.is_none() {
-                return Err(anyhow!(
-                    "Template [{}] does not exist",
-                    template_name.blue()
-                ));
-            }
+            let obsidian_template = obsidian.as_ref().and_then(|obsidian| {
+                let path = obsidian.template_path(&template_name)?;
+                path.is_file().then_some((obsidian, path))
+            });
+
+            if let Some((obsidian, template_path)) = obsidian_template {
+                let template = std::fs::read_to_string(template_path)?;
+                let new_note = Note::create(note_path.clone())?;
+                new_note.write(&obsidian.render_template(&template, &name))?;
+            } else {
+                let maybe_template = item_with_name::<Note>(&templates, &template_name);
+
+                if maybe_template.is_none() {
+                    return Err(Error::TemplateNotFound(template_name));
+                }
 
-            let new_note = Note::create(note_path)?;
+                let new_note = Note::create(note_path.clone())?;
 
-            Editor::copy_note(maybe_template.unwrap(), &new_note)?;
+                Editor::copy_note(maybe_template.unwrap(), &new_note)?;
+            }
         } else {
-            Note::create(note_path)?;
+            Note::create(note_path.clone())?;
         }
+        self.record(OperationKind::Create, ItemType::Nt, None, note_path)?;
 
-        return Ok(Message::ItemCreated(ItemType::Nt, name.to_owned()));
+        return Ok(Message::ItemCreated(ItemType::Nt, name));
     }
 
     pub fn today(&mut self) -> JeffResult<Message> {
-        let daily_note_name = daily_note_name();
-        let vault = self.vaults.ref_current()?;
+        let (note_path, contents) = self.daily_note()?;
+        let daily_note_name = note_path.file_stem().unwrap().to_string_lossy().to_string();
         let mut message = Message::Empty;
-        let daily_note = match vault.get_note_with_name(&daily_note_name) {
-            Ok(note) => note,
-            Err(_) => {
-                // daily note does does not exist
-                #[cfg(not(test))]
-                let create_daily_note = Confirm::with_theme(&ColorfulTheme::default())
-                    .with_prompt(format!("Create daily note {}?", daily_note_name))
-                    .interact()
-                    .unwrap();
-                #[cfg(test)]
-                let create_daily_note = true;
-
-                if create_daily_note {
-                    let note_path = Note::generate_abs_path(vault.get_location(), &daily_note_name);
-                    message = Message::ItemCreated(ItemType::Nt, daily_note_name);
-                    Note::create(note_path)?
-                } else {
-                    return Err(anyhow!("Daily note does not exist"));
-                }
+        let daily_note = if note_path.is_file() {
+            Note::load(note_path)?
+        } else {
+            // daily note does does not exist
+            let create_daily_note = self
+                .prompts
+                .confirm(format!("Create daily note {}?", daily_note_name))?;
+
+            if create_daily_note {
+                message = Message::ItemCreated(ItemType::Nt, daily_note_name);
+                self.create_note_with_contents(note_path, &contents)?
+            } else {
+                return Err(Error::ItemNotFound(ItemType::Nt, daily_note_name));
             }
         };
 
-        self.editor.open_note(daily_note)?;
+        self.edit_note(daily_note)?;
 
         Ok(message)
     }
 
+    /// Returns the path of today's daily note in the current vault, and the
+    /// contents of a new daily note. Obsidian vaults use their daily note
+    /// settings.
+    fn daily_note(&self) -> JeffResult<(PathBuf, String)> {
+        let vault = self.vaults.ref_current()?;
+
+        match Obsidian::load(vault.get_location())? {
+            Some(obsidian) => {
+                let now = Local::now().naive_local();
+                let note_path = obsidian.daily_note_path(&now);
+                let title = note_path.file_stem().unwrap().to_string_lossy().to_string();
+                let contents = obsidian.daily_note_contents(&now, &title)?;
+                Ok((note_path, contents))
+            }
+            None => Ok((
+                Note::generate_abs_path_with_extension(
+                    vault.get_location(),
+                    &daily_note_name(),
+                    &vault.default_note_extension(),
+                ),
+                String::new(),
+            )),
+        }
+    }
+
+    /// Creates the note at `note_path`, and any missing folders above it.
+    fn create_note_with_contents(
+        &mut self,
+        note_path: PathBuf,
+        contents: &str,
+    ) -> JeffResult<Note> {
+        if let Some(folder) = note_path.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+        let note = Note::create(note_path.clone())?;
+        note.write(contents)?;
+        self.record(OperationKind::Create, ItemType::Nt, None, note_path)?;
+
+        Ok(note)
+    }
+
     #[cfg(test)]
     pub fn open_note(&mut self, name: &String) -> JeffResult<Message> {
         let note = self
             .vaults
             .ref_current()?
             .get_note_from_active_folder(name)?;
-        self.editor.open_note(note)?;
+        self.edit_note(note)?;
         Ok(Message::Empty)
     }
 
     pub fn template(&mut self, name: &Option<String>) -> JeffResult<Message> {
         if name.is_none() {
-            self.templates.list();
-            return Ok(Message::Empty);
+            return Ok(Message::Templates(self.templates.tree()));
         }
 
         let name = name.to_owned().unwrap();
@@ -143,20 +278,21 @@ impl App {
         let maybe_template = item_with_name::<Note>(&templates, &name);
 
         if let Some(template) = maybe_template {
-            self.editor.open_note(template.to_owned())?;
+            self.edit_note(template.to_owned())?;
 
             return Ok(Message::Empty);
         }
 
-        let create_template = confirmation_prompt(format!(
+        let create_template = self.prompts.confirm(format!(
             "Would you like to create a template [{}]",
             name.blue()
-        ));
+        ))?;
 
         if create_template {
             let template_path = Note::generate_abs_path(self.templates.get_location(), &name);
-            let template = Note::create(template_path)?;
-            self.editor.open_note(template.to_owned())?;
+            let template = Note::create(template_path.clone())?;
+            self.changed(format!("create template {}", name), vec![template_path]);
+            self.edit_note(template.to_owned())?;
 
             Ok(Message::TemplateCreated(name.to_owned()))
         } else {
@@ -176,10 +312,14 @@ impl App {
          * for a note.
          */
         if let Some(note) = maybe_note {
-            self.editor.open_note(note.clone())?;
+            self.edit_note(note.clone())?;
             return Ok(Message::Empty);
         }
 
+        if !self.prompts.is_interactive() {
+            return Err(Error::ItemNotFound(ItemType::Note, name.to_owned()));
+        }
+
         let mut selections = vec![];
 
         for note in &notes {
@@ -201,31 +341,322 @@ impl App {
                 .iter()
                 .find(|note| note.get_name() == note_name)
                 .unwrap();
-            self.editor.open_note(note.to_owned())?;
+            self.edit_note(note.to_owned())?;
 
             Ok(Message::Empty)
         } else {
-            Err(anyhow!(Error::ItemNotFound(
-                ItemType::Note,
-                name.to_owned()
-            )))
+            Err(Error::ItemNotFound(ItemType::Note, name.to_owned()))
         }
     }
 
+    pub fn append_to_note(
+        &mut self,
+        name: &String,
+        text: &Option<String>,
+        under: &Option<String>,
+        timestamp: bool,
+    ) -> JeffResult<Message> {
+        let note = self
+            .vaults
+            .ref_current()?
+            .get_note_from_active_folder(name)?;
+        let text = App::text_or_stdin(text, timestamp)?;
+
+        note.append(&text, under.as_deref())?;
+        let paths = vec![note.get_location().to_path_buf()];
+        self.changed(format!("append to note {}", name), paths);
+
+        Ok(Message::NoteAppended(name.to_owned()))
+    }
+
+    pub fn prepend_to_note(
+        &mut self,
+        name: &String,
+        text: &Option<String>,
+        under: &Option<String>,
+        timestamp: bool,
+    ) -> JeffResult<Message> {
+        let note = self
+            .vaults
+            .ref_current()?
+            .get_note_from_active_folder(name)?;
+        let text = App::text_or_stdin(text, timestamp)?;
+
+        note.prepend(&text, under.as_deref())?;
+        let paths = vec![note.get_location().to_path_buf()];
+        self.changed(format!("prepend to note {}", name), paths);
+
+        Ok(Message::NotePrepended(name.to_owned()))
+    }
+
+    /// Appends a timestamped bullet to the inbox note of the current vault,
+    /// creating the inbox if it does not exist. Never prompts, so that it can
+    /// be run without a terminal.
+    pub fn capture(&mut self, text: &Option<String>) -> JeffResult<Message> {
+        let vault = self.vaults.ref_current()?;
+        // read the text first, so that no inbox is left behind without it
+        let text = App::text_or_stdin(text, true)?;
+        let inbox = CONFIG.lock().unwrap().get_inbox();
+        let (inbox_path, contents) = match inbox {
+            Some(inbox_name) => {
+                // the inbox may name its extension, e.g. `inbox.txt`
+                let extension = vault.default_note_extension();
+                let (name, extension) = match Note::split_extension(&inbox_name) {
+                    (name, Some(extension)) => (name.to_string(), extension.to_string()),
+                    (name, None) => (name.to_string(), extension),
+                };
+                let inbox_path =
+                    Note::generate_abs_path_with_extension(vault.get_location(), &name, &extension);
+                (inbox_path, String::new())
+            }
+            None => self.daily_note()?,
+        };
+
+        let inbox = if inbox_path.is_file() {
+            Note::load(inbox_path)?
+        } else {
+            self.create_note_with_contents(inbox_path, &contents)?
+        };
+        let inbox_name = inbox.get_name();
+
+        let bullet = format!("- {}", text.replace('\n', "\n  "));
+        inbox.append(&bullet, None)?;
+        let paths = vec![inbox.get_location().to_path_buf()];
+        self.changed(format!("capture to note {}", inbox_name), paths);
+
+        Ok(Message::NoteAppended(inbox_name))
+    }
+
+    /// Returns the given text, or reads it from stdin if it is missing or "-".
+    /// The text is optionally prefixed with the current time.
+    fn text_or_stdin(text: &Option<String>, timestamp: bool) -> JeffResult<String> {
+        let text = match text {
+            Some(text) if text != "-" => text.to_owned(),
+            _ => read_stdin()?,
+        };
+        let text = text.trim_end_matches(&['\r', '\n'][..]);
+
+        if text.is_empty() {
+            return Err(Error::EmptyText);
+        }
+
+        if timestamp {
+            Ok(format!("{} {}", generate_time_string(), text))
+        } else {
+            Ok(text.to_owned())
+        }
+    }
+
+    /// Returns a note from the active folder, and its earlier versions.
+    fn note_revisions(&self, name: &String) -> JeffResult<(Note, NoteRevisions)> {
+        let vault = self.vaults.ref_current()?;
+        let note = vault.get_note_from_active_folder(name)?;
+        let revisions = NoteRevisions::open(vault.get_location(), note.get_location());
+
+        Ok((note, revisions))
+    }
+
+    pub fn log_note(&self, name: &String) -> JeffResult<Message> {
+        let (_, revisions) = self.note_revisions(name)?;
+        Ok(Message::Revisions(name.to_owned(), revisions.revisions()?))
+    }
+
+    /// Compares a note with the given revision, or with the last revision
+    /// that differs from it.
+    pub fn diff_note(&self, name: &String, revision: &Option<String>) -> JeffResult<Message> {
+        let (note, revisions) = self.note_revisions(name)?;
+        let revision = match revision {
+            Some(id) => revisions.find(id)?,
+            None => revisions
+                .last_change()?
+                .ok_or_else(|| Error::RevisionNotFound(String::new()))?,
+        };
+
+        let old = revisions.contents(&revision)?;
+        let new = std::fs::read_to_string(note.get_location().as_path())?;
+
+        Ok(Message::Diff(
+            name.to_owned(),
+            revision.id,
+            diff_lines(&old, &new),
+        ))
+    }
+
+    /// Rolls a note back to the given revision. The current contents are
+    /// kept as a revision first.
+    pub fn restore_note(&mut self, name: &String, revision: &str) -> JeffResult<Message> {
+        let (note, revisions) = self.note_revisions(name)?;
+        let revision = revisions.find(revision)?;
+        let contents = revisions.contents(&revision)?;
+
+        // so that the restore can itself be rolled back
+        revisions.save(&format!("jf: save note {} before restoring it", name))?;
+        std::fs::write(note.get_location().as_path(), contents)?;
+
+        let paths = vec![note.get_location().to_path_buf()];
+        self.changed(format!("restore note {} to {}", name, revision.id), paths);
+
+        Ok(Message::NoteRestored(name.to_owned(), revision.id))
+    }
+
+    /// Copies a file into the attachment folder, unless it is already there,
+    /// and embeds it at the end of a note.
+    pub fn attach(
+        &mut self,
+        name: &String,
+        file: &Path,
+        link: &Option<LinkStyle>,
+    ) -> JeffResult<Message> {
+        if !file.is_file() {
+            return Err(Error::InvalidPath(file.to_path_buf()));
+        }
+
+        let vault = self.vaults.ref_current()?;
+        let vault_path = vault.get_location().to_path_buf();
+        let note = vault.get_note_from_active_folder(name)?;
+        let note_path = note.get_location().to_path_buf();
+
+        let folder = attachments::attachment_folder(&vault_path, &note.get_location().parent())?;
+        let (attachment, copied) = attachments::store(file, &folder)?;
+        let style = match link {
+            Some(style) => *style,
+            None => attachments::link_style(&vault_path)?,
+        };
+        let files = VaultFiles::load(&vault_path)?;
+        note.append(&files.embed(&note_path, &attachment, style), None)?;
+
+        let relative_path = files.relative(&attachment);
+        let description = format!("attach {} to note {}", relative_path.display(), name);
+        let mut paths = vec![note_path];
+        if copied {
+            paths.push(attachment);
+        }
+        self.changed(description, paths);
+
+        Ok(Message::Attached(name.to_owned(), relative_path, copied))
+    }
+
+    /// Lists the attachments of the current vault, only the ones that no note
+    /// links to, or the embeds whose file is missing.
+    pub fn attachments(&self, orphans: bool, missing: bool) -> JeffResult<Message> {
+        let vault = self.vaults.ref_current()?;
+        let files = VaultFiles::load(vault.get_location())?;
+
+        if missing {
+            return Ok(Message::MissingEmbeds(files.missing()?));
+        }
+
+        let attachments = if orphans {
+            files.orphans()?
+        } else {
+            files
+                .attachments()
+                .iter()
+                .map(|attachment| files.relative(attachment))
+                .collect()
+        };
+
+        Ok(Message::Attachments(attachments))
+    }
+
+    pub fn export(&self, action: &ExportAction) -> JeffResult<Message> {
+        let vault = self.vaults.ref_current()?;
+        let name = self.vaults.current_name().unwrap_or_default();
+
+        match action {
+            ExportAction::Html { out } => {
+                let notes = export::export_html(&name, vault.get_location(), out)?;
+
+                Ok(Message::Exported(out.to_owned(), notes))
+            }
+            ExportAction::Bundle { name, inline_links } => {
+                let notes = match vault.get_note_from_active_folder(name) {
+                    Ok(note) => vec![note],
+                    Err(Error::ItemNotFound(..)) => vault
+                        .get_folder_from_active_folder(name)?
+                        .notes_in_tree_order(),
+                    Err(error) => return Err(error),
+                };
+                let paths: Vec<PathBuf> = notes
+                    .iter()
+                    .map(|note| note.get_location().to_path_buf())
+                    .collect();
+
+                let bundle = export::bundle(vault.get_location(), &paths, *inline_links)?;
+
+                Ok(Message::Bundle(bundle))
+            }
+        }
+    }
+
+    /**
+     * Checks the current vault and the registered vaults for problems. With
+     * `fix`, a stale current folder is reset to the root of the vault. The
+     * other problems are only reported.
+     */
+    pub fn doctor(&mut self, fix: bool) -> JeffResult<Message> {
+        let mut problems = vec![];
+
+        let current = match self.vaults.ref_current() {
+            Ok(vault) => Some(vault),
+            // the registry check reports a current vault that is missing
+            Err(_) if self.vaults.missing_current().is_some() => None,
+            Err(error) => return Err(error),
+        };
+        if let Some(vault) = current {
+            let name = self.vaults.current_name().unwrap_or_default();
+            let vault_path = vault.get_location().to_path_buf();
+            problems.extend(doctor::check_vault(&vault_path)?);
+
+            let folder = vault.get_active_folder_path().unwrap_or_default();
+            if !folder.is_empty() {
+                problems.extend(doctor::check_current_folder(&name, &vault_path, &folder));
+            }
+        }
+        problems.extend(doctor::check_registry(&self.vaults.list_vaults()));
+
+        if fix {
+            // missing vaults aren't unregistered, they may only be on a drive
+            // that isn't plugged in
+            for problem in problems.iter_mut() {
+                if problem.check == Check::StaleFolder {
+                    let vault = self.vaults.mut_current()?;
+                    vault.set_active_folder(None)?;
+                    let vault_path = vault.get_location().to_path_buf();
+                    let description = format!("reset current folder of vault {}", problem.subject);
+                    self.changed(description, vec![vault_path]);
+                    problem.fixed = true;
+                }
+            }
+        }
+
+        Ok(Message::Doctor(problems))
+    }
+
+    pub fn tui(&mut self) -> JeffResult<Message> {
+        Tui::new(self)?.run()?;
+
+        Ok(Message::Empty)
+    }
+
+    pub fn shell(&mut self) -> JeffResult<Message> {
+        Shell::new()?.run(self)?;
+
+        Ok(Message::Empty)
+    }
+
     pub fn create_folder(&mut self, name: &String) -> JeffResult<Message> {
         let vault = self.vaults.ref_current()?;
 
         let maybe_folder = vault.get_folder_with_name(name);
         if let Ok(folder) = maybe_folder {
-            return Err(anyhow!(
-                "Folder with name [{}] already exists",
-                folder.get_name()
-            ));
+            return Err(Error::ItemAlreadyExists(ItemType::Fd, folder.get_name()));
         }
 
         let folder_path = Folder::generate_abs_path(&vault.get_active_location(), name);
 
-        Folder::create(folder_path)?;
+        Folder::create(folder_path.clone())?;
+        self.record(OperationKind::Create, ItemType::Fd, None, folder_path)?;
 
         return Ok(Message::ItemCreated(ItemType::Fd, name.to_owned()));
     }
@@ -239,57 +670,250 @@ impl App {
 
     pub fn remove_item(&mut self, item_type: ItemType, name: &String) -> JeffResult<Message> {
         // display a dialog to confirm the action
-        #[cfg(not(test))]
-        let remove_item = confirmation_prompt(format!("Are you sure you want to remove {}?", name));
-        #[cfg(test)]
-        let remove_item = true;
+        let remove_item = self
+            .prompts
+            .confirm(format!("Are you sure you want to remove {}?", name))?;
 
         if !remove_item {
             return Ok(Message::Empty);
         }
 
-        match item_type {
+        self.delete_item(Scope::Vault, item_type, name)
+    }
+
+    /// Removes an item without asking for confirmation.
+    pub fn delete_item(
+        &mut self,
+        scope: Scope,
+        item_type: ItemType,
+        name: &String,
+    ) -> JeffResult<Message> {
+        let (before, after) = match item_type {
             ItemType::Fd | ItemType::Folder => {
                 let vault = self.vaults.ref_current()?;
-                let folder = vault.get_folder_with_name(name)?;
-                folder.delete()?;
+                let folder = App::find_folder(vault, scope, name)?;
+                let after = App::move_to_trash(vault, item_type, folder.get_location())?;
+                (folder.get_location().to_path_buf(), after)
             }
             ItemType::Nt | ItemType::Note => {
                 let vault = self.vaults.ref_current()?;
                 let note = vault.get_note_from_active_folder(name)?;
-                note.delete()?;
+                let after = App::move_to_trash(vault, item_type, note.get_location())?;
+                (note.get_location().to_path_buf(), after)
             }
             ItemType::Vl | ItemType::Vault => {
-                self.vaults.remove_vault(name)?;
+                let before = self.vaults.get_vault_path(name)?;
+                if !before.is_dir() {
+                    // there is nothing to move to the trash
+                    self.vaults.unregister_vault(name)?;
+                    return Ok(Message::VaultUnregistered(name.to_owned()));
+                }
+                (before, self.vaults.remove_vault(name)?)
             }
         };
+        self.record(OperationKind::Remove, item_type, Some(before), after)?;
 
         Ok(Message::ItemRemoved(item_type.to_owned(), name.to_owned()))
     }
 
+    fn find_folder(vault: &Vault, scope: Scope, name: &String) -> JeffResult<Folder> {
+        match scope {
+            Scope::Vault => vault.get_folder_with_name(name),
+            Scope::ActiveFolder => vault.get_folder_from_active_folder(name),
+        }
+    }
+
+    /// Moves an item of the vault to its trash. Returns the path of the item
+    /// inside of the trash.
+    fn move_to_trash(vault: &Vault, item_type: ItemType, path: &Path) -> JeffResult<PathBuf> {
+        let original_path = path
+            .strip_prefix(vault.get_location().as_path())
+            .map_err(|_| Error::OutOfBounds)?
+            .to_path_buf();
+        let mut trash = Trash::for_vault(vault)?;
+        let item = trash.put(item_type, path, original_path)?;
+
+        Ok(trash.item_path(&item))
+    }
+
+    /// Records an operation in the journal, so that it can be undone.
+    fn record(
+        &mut self,
+        kind: OperationKind,
+        item_type: ItemType,
+        before: Option<PathBuf>,
+        after: PathBuf,
+    ) -> JeffResult<()> {
+        let operation = Operation::new(kind, item_type, before, after);
+        let paths = operation.before.iter().chain([&operation.after]).cloned();
+        self.changed(operation.describe(), paths.collect());
+
+        Journal::record(operation)
+    }
+
+    /// Notes that a command changed the given files.
+    fn changed(&mut self, description: String, paths: Vec<PathBuf>) {
+        self.changes.push(Change { description, paths });
+    }
+
+    /// Reverses the last `count` operations in the journal, most recent first.
+    /// Stops at the first operation whose files have changed since.
+    pub fn undo(&mut self, count: usize) -> JeffResult<Message> {
+        let mut journal = Journal::load()?;
+        let mut undone = vec![];
+
+        for _ in 0..count {
+            let Some(operation) = journal.last().cloned() else {
+                break;
+            };
+            if !operation.is_unchanged() {
+                return Err(Error::ChangedSinceOperation(operation.describe()));
+            }
+
+            self.undo_operation(&operation)?;
+            journal.pop()?;
+            let paths = operation.before.iter().chain([&operation.after]).cloned();
+            self.changed(format!("undo {}", operation.describe()), paths.collect());
+            undone.push(operation.describe());
+        }
+
+        if undone.is_empty() {
+            return Err(Error::NothingToUndo);
+        }
+
+        Ok(Message::Undone(undone))
+    }
+
+    fn undo_operation(&mut self, operation: &Operation) -> JeffResult<()> {
+        let after = operation.after.as_path();
+        let name = |path: &Path| {
+            path.file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().to_string())
+        };
+        // vaults are registered under their own name, which may not be the
+        // name of their folder
+        let vault_name = |path: &Path| self.vaults.vault_name(path).unwrap_or_else(|| name(path));
+
+        match (operation.kind, &operation.before, operation.item_type) {
+            (OperationKind::Create, _, ItemType::Vl | ItemType::Vault) => {
+                self.vaults.remove_vault(&vault_name(after))?;
+            }
+            (OperationKind::Create, _, item_type) => {
+                let vault_path = Vault::root_of(after).ok_or(Error::NotInsideVault)?;
+                let vault = Vault::load(vault_path)?;
+                App::move_to_trash(&vault, item_type, after)?;
+            }
+            (OperationKind::Remove, Some(_), ItemType::Vl | ItemType::Vault) => {
+                self.vaults.restore_vault(&name(after))?;
+            }
+            (OperationKind::Remove, Some(before), _) => {
+                let mut trash = Trash::open(after.parent().unwrap().to_path_buf())?;
+                let item = trash
+                    .find(&name(after))
+                    .cloned()
+                    .ok_or_else(|| Error::TrashItemNotFound(name(after)))?;
+                trash.restore_to(&item, before)?;
+            }
+            (OperationKind::Rename, Some(before), ItemType::Vl | ItemType::Vault) => {
+                self.vaults
+                    .rename_vault(&vault_name(after), &name(before))?;
+            }
+            (OperationKind::Move, Some(before), ItemType::Vl | ItemType::Vault) => {
+                self.vaults
+                    .move_vault(&vault_name(after), before.parent().unwrap())?;
+            }
+            (_, Some(before), item_type) => {
+                if before.exists() {
+                    return Err(Error::ItemAlreadyExists(item_type, name(before)));
+                }
+                std::fs::rename(after, before)?;
+            }
+            (_, None, _) => return Err(Error::InternalError),
+        }
+
+        Ok(())
+    }
+
+    pub fn trash(&mut self, action: &Option<TrashAction>, vaults: bool) -> JeffResult<Message> {
+        let (mut trash, root) = if vaults {
+            (Trash::for_vaults()?, PathBuf::new())
+        } else {
+            let vault = self.vaults.ref_current()?;
+            (Trash::for_vault(vault)?, vault.get_location().to_path_buf())
+        };
+
+        match action {
+            None | Some(TrashAction::List) => Ok(Message::Trash(trash.items().clone())),
+            Some(TrashAction::Restore { item }) => {
+                let item = if vaults {
+                    self.vaults.restore_vault(item)?
+                } else {
+                    trash.restore(item, &root)?
+                };
+                let description = format!("restore {} {}", item.item_type.full(), item.name());
+                self.changed(description, vec![root.join(&item.original_path)]);
+                Ok(Message::ItemRestored(item.item_type, item.name()))
+            }
+            Some(TrashAction::Empty { older_than }) => {
+                let delete = self
+                    .prompts
+                    .confirm("Permanently delete the removed items?".to_string())?;
+                if !delete {
+                    return Ok(Message::Empty);
+                }
+
+                let deleted = trash.empty(*older_than)?;
+                let paths = deleted.iter().map(|item| trash.item_path(item));
+                self.changed("empty trash".to_string(), paths.collect());
+
+                Ok(Message::TrashEmptied(deleted.len()))
+            }
+        }
+    }
+
     pub fn rename_item(
         &mut self,
         item_type: ItemType,
         name: &String,
         new_name: &String,
     ) -> JeffResult<Message> {
-        match item_type {
+        self.rename_item_in(Scope::Vault, item_type, name, new_name)
+    }
+
+    pub fn rename_item_in(
+        &mut self,
+        scope: Scope,
+        item_type: ItemType,
+        name: &String,
+        new_name: &String,
+    ) -> JeffResult<Message> {
+        let (before, after) = match item_type {
             ItemType::Fd | ItemType::Folder => {
                 let vault = self.vaults.ref_current()?;
-                let mut folder = vault.get_folder_with_name(name)?;
+                let mut folder = App::find_folder(vault, scope, name)?;
+                let before = folder.get_location().to_path_buf();
 
                 folder.rename(new_name.to_owned())?;
+                (before, folder.get_location().to_path_buf())
             }
             ItemType::Nt | ItemType::Note => {
                 let vault = self.vaults.ref_current()?;
-                let mut note = vault.get_note_with_name(name)?;
+                let mut note = match scope {
+                    Scope::Vault => vault.get_note_with_name(name)?,
+                    Scope::ActiveFolder => vault.get_note_from_active_folder(name)?,
+                };
+                let before = note.get_location().to_path_buf();
 
                 note.rename(new_name.to_owned())?;
+                (before, note.get_location().to_path_buf())
             }
             ItemType::Vl | ItemType::Vault => {
+                let before = self.vaults.get_vault_path(name)?;
                 self.vaults.rename_vault(name, new_name)?;
+                (before, self.vaults.get_vault_path(new_name)?)
             }
-        }
+        };
+        self.record(OperationKind::Rename, item_type, Some(before), after)?;
 
         return Ok(Message::ItemRenamed(
             item_type.to_owned(),
@@ -300,20 +924,17 @@ impl App {
 
     pub fn list(&self) -> JeffResult<Message> {
         let vault = self.vaults.ref_current()?;
+        let vault_name = self.vaults.current_name().unwrap_or_default();
 
         if let Ok(Some(active_folder)) = vault.get_active_folder() {
-            println!(
-                "{} > {}",
-                vault.to_display_string(),
-                active_folder.to_display_string()
-            );
-            active_folder.list();
+            Ok(Message::List(
+                vault_name,
+                Some(active_folder.get_name()),
+                active_folder.tree(),
+            ))
         } else {
-            println!("{}", vault.to_display_string());
-            vault.list();
+            Ok(Message::List(vault_name, None, vault.tree()))
         }
-
-        return Ok(Message::Empty);
     }
 
     pub fn set_config(
@@ -322,31 +943,58 @@ impl App {
         maybe_value: Option<String>,
     ) -> JeffResult<Message> {
         if config_type.is_none() {
-            return Ok(Message::Custom(format!(
-                "\nConfiguration\n---\n{}",
-                CONFIG.lock().unwrap()
-            )));
+            return Ok(Message::ShowConfig(CONFIG.lock().unwrap().clone()));
         }
 
         let config_type = config_type.unwrap();
-        let value = match config_type {
-            ConfigType::Editor => maybe_value.unwrap(),
-            ConfigType::Conflict => maybe_value.unwrap(),
-            ConfigType::VaultColor => {
-                maybe_value.unwrap_or_else(|| display_item_color_select::<Vault>())
+        let value = match (&config_type, maybe_value) {
+            (ConfigType::Inbox, Some(value)) if !valid_name(&value) => {
+                return Err(Error::InvalidName)
             }
-            ConfigType::FolderColor => {
-                maybe_value.unwrap_or_else(|| display_item_color_select::<Folder>())
+            (_, Some(value)) => value,
+            (ConfigType::Inbox, None) => String::new(),
+            (ConfigType::VaultColor, None) if self.prompts.is_interactive() => {
+                display_item_color_select::<Vault>()?
             }
-            ConfigType::NoteColor => {
-                maybe_value.unwrap_or_else(|| display_item_color_select::<Note>())
+            (ConfigType::FolderColor, None) if self.prompts.is_interactive() => {
+                display_item_color_select::<Folder>()?
             }
+            (ConfigType::NoteColor, None) if self.prompts.is_interactive() => {
+                display_item_color_select::<Note>()?
+            }
+            (_, None) => return Err(Error::MissingValue(config_type.to_str().to_string())),
         };
 
-        CONFIG
-            .lock()
-            .unwrap()
-            .set_config_value(&config_type, value.to_owned());
+        let vault_setting = matches!(
+            config_type,
+            ConfigType::GitAutocommit | ConfigType::NoteExtensions | ConfigType::NoteExtension
+        );
+        match config_type {
+            ConfigType::GitAutocommit => {
+                let autocommit = value
+                    .parse()
+                    .map_err(|_| Error::InvalidInput(value.to_owned()))?;
+                self.vaults.mut_current()?.set_git_autocommit(autocommit)?;
+            }
+            ConfigType::NoteExtensions => {
+                let extensions = value.split(',').map(str::to_string).collect();
+                self.vaults.mut_current()?.set_note_extensions(extensions)?;
+            }
+            ConfigType::NoteExtension => {
+                self.vaults
+                    .mut_current()?
+                    .set_default_note_extension(value.to_owned())?;
+            }
+            _ => CONFIG
+                .lock()
+                .unwrap()
+                .set_config_value(&config_type, value.to_owned())?,
+        }
+        if vault_setting {
+            let vault_path = self.vaults.ref_current()?.get_location().to_path_buf();
+            let description = format!("set {} to {}", config_type.to_str(), value);
+            self.changed(description, vec![vault_path]);
+        }
         return Ok(Message::Config(config_type.to_owned(), value.to_owned()));
     }
 
@@ -356,18 +1004,30 @@ impl App {
         name: &String,
         new_location: &PathBuf,
     ) -> JeffResult<Message> {
-        match item_type {
+        self.move_item_in(Scope::Vault, item_type, name, new_location)
+    }
+
+    pub fn move_item_in(
+        &mut self,
+        scope: Scope,
+        item_type: ItemType,
+        name: &String,
+        new_location: &PathBuf,
+    ) -> JeffResult<Message> {
+        let (before, after) = match item_type {
             ItemType::Fd | ItemType::Folder => {
                 // new location is relative to the root of the vault
                 let vault = self.vaults.ref_current()?;
-                let mut folder = vault.get_folder_with_name(name)?;
+                let mut folder = App::find_folder(vault, scope, name)?;
                 let new_absolute_path = process_path(&join_paths(vec![
                     vault.get_location().as_path(),
                     new_location,
                     &PathBuf::from(folder.get_name()),
                 ]));
 
+                let before = folder.get_location().to_path_buf();
                 folder.relocate(new_absolute_path.to_owned())?;
+                (before, new_absolute_path)
             }
             ItemType::Nt | ItemType::Note => {
                 /*
@@ -383,12 +1043,17 @@ impl App {
                     &PathBuf::from(note.get_full_name()),
                 ]));
 
+                let before = note.get_location().to_path_buf();
                 note.relocate(new_absolute_path.to_owned())?;
+                (before, new_absolute_path)
             }
             ItemType::Vl | ItemType::Vault => {
+                let before = self.vaults.get_vault_path(name)?;
                 self.vaults.move_vault(name, new_location)?;
+                (before, self.vaults.get_vault_path(name)?)
             }
-        }
+        };
+        self.record(OperationKind::Move, item_type, Some(before), after)?;
 
         return Ok(Message::ItemMoved(item_type.to_owned(), name.to_owned()));
     }
@@ -402,7 +1067,7 @@ impl App {
         let vault = self.vaults.ref_current()?;
         let new_vault = self.vaults.get_vault(vault_name)?;
 
-        match item_type {
+        let (before, after) = match item_type {
             VaultItemType::Fd | VaultItemType::Folder => {
                 // new location is relative to the root of the vault
                 let mut folder = vault.get_folder_with_name(name)?;
@@ -411,7 +1076,9 @@ impl App {
                     &PathBuf::from(folder.get_name()),
                 ]));
 
+                let before = folder.get_location().to_path_buf();
                 folder.relocate(new_absolute_path.to_owned())?;
+                (before, new_absolute_path)
             }
             VaultItemType::Nt | VaultItemType::Note => {
                 // new location is relative to the root of the vault
@@ -422,9 +1089,17 @@ impl App {
                     &PathBuf::from(note.get_full_name()),
                 ]));
 
+                let before = note.get_location().to_path_buf();
                 note.relocate(new_absolute_path.to_owned())?;
+                (before, new_absolute_path)
             }
-        }
+        };
+        self.record(
+            OperationKind::Vmove,
+            item_type.to_item(),
+            Some(before),
+            after,
+        )?;
 
         return Ok(Message::ItemVMoved(
             item_type.to_owned(),
@@ -436,6 +1111,7 @@ impl App {
 
 impl App {
     pub fn new() -> JeffResult<Self> {
+        *CONFIG.lock().unwrap() = Config::load()?;
         let editor_data = CONFIG.lock().unwrap().get_editor_data();
         let templates_path = application_templates_path();
 
@@ -447,27 +1123,303 @@ impl App {
             vaults: Vaults::load()?,
             editor: Editor::from_config(editor_data),
             templates: Folder::load(templates_path)?,
+            prompts: Prompts::default(),
+            command_line: None,
+            changes: vec![],
+            committed: 0,
         })
     }
 
-    #[rustfmt::skip]
+    /// Loads the app, offering to back up and reset every config or data file
+    /// that can't be parsed.
+    pub fn with_prompts(prompts: Prompts) -> JeffResult<Self> {
+        loop {
+            match App::new() {
+                Ok(mut app) => {
+                    if let Some(error) = app.vaults.missing_current() {
+                        let warning = format!("{}, continuing without a current vault", error);
+                        eprintln!("{}", Output::Warning(warning));
+                    }
+                    app.set_prompts(prompts);
+                    return Ok(app);
+                }
+                Err(Error::CorruptedFile(path, error)) => {
+                    let reset = prompts
+                        .confirm(format!(
+                            "Couldn't parse {}: {}\nBack it up and reset it?",
+                            path.display(),
+                            error
+                        ))
+                        .unwrap_or(false);
+
+                    if !reset {
+                        return Err(Error::CorruptedFile(path, error));
+                    }
+
+                    let backup = back_up_file(&path)?;
+                    eprintln!("backed up {} to {}", path.display(), backup.display());
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Reloads the current vault and the templates from disk.
+    pub fn reload(&mut self) -> JeffResult<()> {
+        self.vaults.load_current_vault()?;
+        self.templates = Folder::load(application_templates_path())?;
+
+        Ok(())
+    }
+
+    pub fn set_prompts(&mut self, prompts: Prompts) {
+        self.prompts = prompts;
+    }
+
+    pub fn current_vault(&self) -> JeffResult<&Vault> {
+        self.vaults.ref_current()
+    }
+
+    /// Sets the active folder of the current vault, given as a path relative to
+    /// the vault.
+    pub fn set_active_folder(&mut self, folder_path: Option<String>) -> JeffResult<()> {
+        self.vaults.mut_current()?.set_active_folder(folder_path)
+    }
+
+    /// Returns the name of the current vault and the path to its active folder.
+    pub fn location(&self) -> Option<(String, Option<String>)> {
+        let vault = self.vaults.ref_current().ok()?;
+        let folder = vault
+            .get_active_folder_path()
+            .filter(|folder| !folder.is_empty());
+
+        Some((self.vaults.current_name()?, folder))
+    }
+
+    /// Returns the names of the vaults, the templates, and the items inside of
+    /// the active folder.
+    pub fn item_names(&self) -> Vec<String> {
+        let mut names = self.vaults.vault_names();
+        names.extend(self.templates.notes().iter().map(|note| note.get_name()));
+
+        if let Ok(collection) = self
+            .vaults
+            .ref_current()
+            .and_then(|vault| vault.active_collection())
+        {
+            names.extend(collection.folders().iter().map(|folder| folder.get_name()));
+            names.extend(collection.notes().iter().map(|note| note.get_name()));
+        }
+
+        names
+    }
+
+    /// Runs a command, then logs and commits the files it changed.
     pub fn handle_command(&mut self, command: Command) -> JeffResult<Message> {
-        match &command {
-            Command::Vault { show_loc, name, location, } => self.vault(*show_loc, name, location),
+        self.changes.clear();
+        self.committed = 0;
+        let result = self.run_command(&command);
+
+        // changes made before a command failed are still logged and committed
+        if !matches!(command, Command::Shell | Command::Tui) {
+            let command_line = self
+                .command_line
+                .take()
+                .unwrap_or_else(|| format!("{:?}", command));
+            match self.finish_command(command_line) {
+                // the command's changes were made, only committing them failed
+                Err(Error::GitFailed(error)) => {
+                    let warning = format!("changes weren't committed to git: {}", error);
+                    eprintln!("{}", Output::Warning(warning));
+                }
+                Err(error) => {
+                    result?;
+                    return Err(error);
+                }
+                Ok(()) => {}
+            }
+        }
+
+        result
+    }
+
+    /// Sets the command line that the next command was run with.
+    pub fn set_command_line(&mut self, command_line: String) {
+        self.command_line = Some(command_line);
+    }
+
+    /**
+     * Logs the files changed by the last command in the history, and commits
+     * them in vaults that have git autocommit turned on.
+     */
+    pub fn finish_command(&mut self, command_line: String) -> JeffResult<()> {
+        let result = self
+            .write_history(command_line)
+            .and_then(|_| self.autocommit());
+        self.changes.clear();
+        self.committed = 0;
+
+        result
+    }
+
+    fn write_history(&self, command: String) -> JeffResult<()> {
+        let mut paths: Vec<PathBuf> = vec![];
+        for path in self.changes.iter().flat_map(|change| &change.paths) {
+            if !paths.contains(path) {
+                paths.push(path.to_owned());
+            }
+        }
+
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        History::append(&HistoryEntry {
+            time: Local::now(),
+            vault: self.vaults.current_name(),
+            command,
+            paths,
+        })
+    }
+
+    /// Commits the changes that haven't been committed yet.
+    fn autocommit(&mut self) -> JeffResult<()> {
+        let changes = self.changes[self.committed..].to_vec();
+        self.committed = self.changes.len();
+
+        self.commit_to_git(&changes)
+    }
+
+    /// Commits changes in each vault that they touch, if the vault has git
+    /// autocommit turned on.
+    fn commit_to_git(&self, changes: &[Change]) -> JeffResult<()> {
+        for vault in self.vaults.list_vaults() {
+            let vault_path = vault.path;
+            let changes: Vec<&Change> = changes
+                .iter()
+                .filter(|change| {
+                    change
+                        .paths
+                        .iter()
+                        .any(|path| path.starts_with(&vault_path))
+                })
+                .collect();
+            if changes.is_empty() || !vault_path.join(".jeff").is_dir() {
+                continue;
+            }
+
+            let store = VaultStore::load_path(vault_path.join(".jeff/data"))?;
+            if !store.git_autocommit() {
+                continue;
+            }
+
+            let descriptions: Vec<&str> = changes
+                .iter()
+                .map(|change| change.description.as_str())
+                .collect();
+            let paths: Vec<PathBuf> = changes
+                .iter()
+                .flat_map(|change| change.paths.iter().cloned())
+                .collect();
+            let message = format!("jf: {}", descriptions.join(", "));
+            git::commit(&vault_path, &paths, &message)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Opens a note in the editor. Once the editor exits, a snapshot of the
+     * note is taken, or it is committed if its vault has git autocommit
+     * turned on.
+     */
+    fn edit_note(&mut self, note: Note) -> JeffResult<()> {
+        // commit the command's own changes (e.g. creating the note) first
+        self.autocommit()?;
+
+        let revisions = Vault::root_of(note.get_location())
+            .map(|vault_path| NoteRevisions::open(&vault_path, note.get_location()));
+        let edit = Change {
+            description: format!("edit note {}", note.get_name()),
+            paths: vec![note.get_location().to_path_buf()],
+        };
+
+        // snapshots are taken before editing too, to keep changes made
+        // outside of the editor
+        if let Some(revisions) = &revisions {
+            revisions.snapshot()?;
+        }
+        self.editor.open_note(note)?;
+        if let Some(revisions) = &revisions {
+            revisions.snapshot()?;
+        }
+
+        self.commit_to_git(&[edit])
+    }
+
+    /// Returns the history of the given vault (or every vault) since the
+    /// given time.
+    pub fn history(
+        &self,
+        vault: &Option<String>,
+        since: &Option<DateTime<Local>>,
+    ) -> JeffResult<Message> {
+        let vault_path = match vault {
+            Some(vault) => self.vaults.get_vault_path(vault).ok(),
+            None => None,
+        };
+
+        let entries = History::entries()?
+            .into_iter()
+            .filter(|entry| since.is_none_or(|since| entry.time >= since))
+            .filter(|entry| match vault {
+                None => true,
+                Some(vault) => {
+                    entry.vault.as_ref() == Some(vault)
+                        || vault_path.as_ref().is_some_and(|vault_path| {
+                            entry.paths.iter().any(|path| path.starts_with(vault_path))
+                        })
+                }
+            })
+            .collect();
+
+        Ok(Message::History(entries))
+    }
+
+    #[rustfmt::skip]
+    fn run_command(&mut self, command: &Command) -> JeffResult<Message> {
+        match command {
+            Command::Vault { action: Some(action), .. } => self.vault_action(action),
+            Command::Vault { action: None, show_loc, name, location } => self.vault(*show_loc, name, location),
             Command::Enter { name } => self.enter_vault(name),
-            Command::Note { name, from_template, template_name} => self.create_note(name, *from_template, template_name),
+            Command::Note { name, from_template, template_name, extension } => self.create_note(name, *from_template, template_name, extension),
             Command::Today => self.today(),
             // Command::Alias { name, maybe_alias, remove_alias, } => { todo!() }
             Command::Open { name } => self.open_note(name),
+            Command::Append { name, text, under, timestamp } => self.append_to_note(name, text, under, *timestamp),
+            Command::Prepend { name, text, under, timestamp } => self.prepend_to_note(name, text, under, *timestamp),
+            Command::Capture { text } => self.capture(text),
             Command::Folder { name } => self.create_folder(name),
             Command::Chdir { path } => self.change_directory(path),
             Command::Remove { item_type, name } => self.remove_item(*item_type, name),
+            Command::Trash { action, vaults } => self.trash(action, *vaults),
+            Command::Undo { count } => self.undo(*count),
+            Command::History { vault, since } => self.history(vault, since),
+            Command::Log { name } => self.log_note(name),
+            Command::Diff { name, revision } => self.diff_note(name, revision),
+            Command::Restore { name, revision } => self.restore_note(name, revision),
+            Command::Attach { name, file, link } => self.attach(name, file, link),
+            Command::Attachments { orphans, missing } => self.attachments(*orphans, *missing),
+            Command::Doctor { fix } => self.doctor(*fix),
+            Command::Export { action } => self.export(action),
             Command::Rename { item_type, name, new_name, } => self.rename_item(*item_type, name, new_name),
             Command::Move { item_type, name, new_location, } => self.move_item(*item_type, name, new_location),
             Command::Vmove { item_type, name, vault_name, } => self.move_item_to_new_vault(*item_type, name, vault_name),
             Command::List => self.list(),
             Command::Config { config_type, value } => self.set_config(config_type.clone(), value.to_owned()),
             Command::Template { name } => self.template(name),
+            Command::Shell => self.shell(),
+            Command::Tui => self.tui(),
             _ => Ok(Message::Empty),
         }
     }
@@ -481,7 +1433,7 @@ mod test {
     macro_rules! run {
         ( $( $x:expr ),* ) => {
             let mut tests = Vec::new();
-            tests.push(Pass(Command::Vault { show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults())}));
+            tests.push(Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults())}));
             tests.push(Pass(Command::Enter { name: "vault_1".to_string() }));
 
             $(
@@ -496,7 +1448,7 @@ mod test {
     #[test]
     fn note_test() {
         run![
-            Pass(Command::Note { name: "test_note".to_string(), from_template: false, template_name: None }),
+            Pass(Command::Note { name: "test_note".to_string(), from_template: false, template_name: None, extension: None }),
             Pass(Command::Open { name: "test_note".to_string() }),
             Pass(Command::Remove { item_type: ItemType::Nt, name: "test_note".to_string() }),
             Fail(Command::Open { name: "test_note".to_string() }),
@@ -507,17 +1459,17 @@ mod test {
     #[test]
     fn cannot_create_duplicate_vaults() {
         run![
-            Pass(Command::Vault { show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
+            Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
             Pass(Command::Enter { name: "vault_2".to_string() }),
-            Fail(Command::Vault { show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }) // Err: duplicate
+            Fail(Command::Vault { action: None, show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }) // Err: duplicate
         ];
     }
 
     #[test]
     fn move_note_between_vaults() {
         run![
-            Pass(Command::Vault { show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
-            Pass(Command::Note { name: "test_note".to_string(), from_template: false, template_name: None }),
+            Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
+            Pass(Command::Note { name: "test_note".to_string(), from_template: false, template_name: None, extension: None }),
             Pass(Command::Open { name: "test_note".to_string() }),
             Pass(Command::Vmove { item_type: VaultItemType::Nt, name: "test_note".to_string(), vault_name: "vault_2".to_string() }),
             Fail(Command::Open { name: "test_note".to_string() }), // Err: open test_note from vault_1
@@ -529,7 +1481,7 @@ mod test {
     #[test]
     fn move_folder_between_vaults() {
         run![
-            Pass(Command::Vault { show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
+            Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
             Pass(Command::Folder { name: "folder_1".to_string() }),
             Pass(Command::Chdir { path: PathBuf::from("folder_1") }),
             Pass(Command::Vmove { item_type: VaultItemType::Fd, name: "folder_1".to_string(), vault_name: "vault_2".to_string() }),
@@ -544,7 +1496,7 @@ mod test {
         run![
             Pass(Command::Folder { name: "folder_1".to_string() }),
             Pass(Command::Folder { name: "folder_2".to_string() }),
-            Pass(Command::Note { name: "test_note".to_string(), from_template: false, template_name: None }),
+            Pass(Command::Note { name: "test_note".to_string(), from_template: false, template_name: None, extension: None }),
             Pass(Command::Move { item_type: ItemType::Nt, name: "test_note".to_string(), new_location: PathBuf::from("folder_1") }),
             Fail(Command::Open { name: "test_note".to_string() }), // Err: test_note was moved to folder_1 
             Pass(Command::Chdir { path: PathBuf::from("folder_1") }),
@@ -555,6 +1507,21 @@ mod test {
         ];
     }
 
+    #[test]
+    fn folder_commands_start_from_vault_root() {
+        run![
+            Pass(Command::Folder { name: "b".to_string() }),
+            Pass(Command::Chdir { path: PathBuf::from("b") }),
+            Pass(Command::Folder { name: "a".to_string() }),
+            Pass(Command::Chdir { path: PathBuf::from("..") }),
+            Pass(Command::Folder { name: "a".to_string() }),
+            Pass(Command::Chdir { path: PathBuf::from("b") }),
+            Pass(Command::Rename { item_type: ItemType::Fd, name: "a".to_string(), new_name: "c".to_string() }),
+            Pass(Command::Chdir { path: PathBuf::from("../c") }), // the folder at the root was renamed
+            Pass(Command::Chdir { path: PathBuf::from("../b/a") })
+        ];
+    }
+
     #[test]
     fn create_and_remove_folder_note_vault() {
         run! [
@@ -566,14 +1533,14 @@ mod test {
         ];
         run! [
             Fail(Command::Remove { item_type: ItemType::Vault, name: "vault_2".to_string() }),
-            Pass(Command::Vault { show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
+            Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
             Pass(Command::Enter { name: "vault_2".to_string() }),
             Pass(Command::Remove { item_type: ItemType::Vault, name: "vault_2".to_string() }),
             Fail(Command::Enter { name: "vault_2".to_string() })
         ];
         run! [
             Fail(Command::Remove { item_type: ItemType::Note, name: "note_1".to_string() }),
-            Pass(Command::Note { name: "note_1".to_string(), from_template: false, template_name: None }),
+            Pass(Command::Note { name: "note_1".to_string(), from_template: false, template_name: None, extension: None }),
             Pass(Command::Open { name: "note_1".to_string() }),
             Pass(Command::Remove { item_type: ItemType::Note, name: "note_1".to_string() }),
             Fail(Command::Open { name: "note_1".to_string() })
@@ -592,7 +1559,7 @@ mod test {
         run! [
             Pass(Command::Folder { name: "folder_1".to_string() }),
             Pass(Command::Chdir { path: PathBuf::from("folder_1") }),
-            Pass(Command::Note { name: "note_1".to_string(), from_template: false, template_name: None }),
+            Pass(Command::Note { name: "note_1".to_string(), from_template: false, template_name: None, extension: None }),
             Pass(Command::Open { name: "note_1".to_string() }),
             Pass(Command::Chdir { path: PathBuf::from("..") }),
             Fail(Command::Open { name: "note_1".to_string() }), // cannot open note in ./folder_1 from ./
@@ -601,6 +1568,568 @@ mod test {
         ];
     }
 
+    #[test]
+    fn append_and_prepend_to_note() {
+        run! [
+            Fail(Command::Append { name: "note_1".to_string(), text: Some("text".to_string()), under: None, timestamp: false }),
+            Pass(Command::Note { name: "note_1".to_string(), from_template: false, template_name: None, extension: None }),
+            Pass(Command::Append { name: "note_1".to_string(), text: Some("## Tasks".to_string()), under: None, timestamp: false }),
+            Pass(Command::Append { name: "note_1".to_string(), text: Some("- task".to_string()), under: Some("## Tasks".to_string()), timestamp: true }),
+            Pass(Command::Prepend { name: "note_1".to_string(), text: Some("# Note".to_string()), under: None, timestamp: false }),
+            Fail(Command::Prepend { name: "note_1".to_string(), text: Some("text".to_string()), under: Some("## Missing".to_string()), timestamp: false })
+        ];
+    }
+
+    #[test]
+    fn capture_to_inbox() {
+        run! [
+            Pass(Command::Capture { text: Some("idea".to_string()) }), // creates the daily note
+            Pass(Command::Open { name: daily_note_name() }),
+            Pass(Command::Config { config_type: Some(ConfigType::Inbox), value: Some("inbox".to_string()) }),
+            Pass(Command::Capture { text: Some("another idea".to_string()) }), // creates the inbox
+            Pass(Command::Open { name: "inbox".to_string() }),
+            Fail(Command::Config { config_type: Some(ConfigType::Inbox), value: Some("in/box".to_string()) }),
+            Pass(Command::Config { config_type: Some(ConfigType::Inbox), value: None }),
+            Pass(Command::Remove { item_type: ItemType::Nt, name: daily_note_name() }),
+            Fail(Command::Capture { text: Some("\n".to_string()) }), // Err: empty text
+            Fail(Command::Open { name: daily_note_name() }), // Err: no daily note was created
+            // new inboxes use the vault's note extension
+            Pass(Command::Config { config_type: Some(ConfigType::NoteExtension), value: Some("txt".to_string()) }),
+            Pass(Command::Capture { text: Some("idea".to_string()) }),
+            Pass(Command::Open { name: format!("{}.txt", daily_note_name()) }),
+            Pass(Command::Config { config_type: Some(ConfigType::Inbox), value: Some("inbox.md".to_string()) }),
+            Pass(Command::Capture { text: Some("idea".to_string()) }),
+            Pass(Command::Open { name: "inbox.md".to_string() }),
+            Pass(Command::Config { config_type: Some(ConfigType::Inbox), value: None })
+        ];
+    }
+
+    #[test]
+    fn prompts_without_input() {
+        run_test(|| {
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(Command::Note { name: "note_1".to_string(), from_template: false, template_name: None, extension: None }),
+            ]);
+
+            let mut app = App::new().unwrap();
+            app.set_prompts(Prompts::new(false, true));
+            assert!(app.handle_command(Command::Today).is_err());
+            assert!(app.handle_command(Command::Remove { item_type: ItemType::Nt, name: "note_1".to_string() }).is_err());
+            assert!(app.handle_command(Command::Config { config_type: Some(ConfigType::NoteColor), value: None }).is_err());
+
+            app.set_prompts(Prompts::new(true, true));
+            assert!(app.handle_command(Command::Today).is_ok());
+            assert!(app.handle_command(Command::Remove { item_type: ItemType::Nt, name: "note_1".to_string() }).is_ok());
+        });
+    }
+
+    #[test]
+    fn remove_and_restore_from_trash() {
+        let restore = |item: &str| Command::Trash { action: Some(TrashAction::Restore { item: item.to_string() }), vaults: false };
+        let empty = Command::Trash { action: Some(TrashAction::Empty { older_than: None }), vaults: false };
+        run! [
+            Pass(Command::Folder { name: "folder".to_string() }),
+            Pass(Command::Chdir { path: PathBuf::from("folder") }),
+            Pass(Command::Note { name: "note".to_string(), from_template: false, template_name: None, extension: None }),
+            Pass(Command::Remove { item_type: ItemType::Nt, name: "note".to_string() }),
+            Fail(Command::Open { name: "note".to_string() }),
+            Pass(Command::Trash { action: None, vaults: false }),
+            Pass(restore("note")),
+            Pass(Command::Open { name: "note".to_string() }),
+            Fail(restore("note")), // Err: no longer in the trash
+            Pass(Command::Remove { item_type: ItemType::Nt, name: "note".to_string() }),
+            Pass(empty),
+            Fail(restore("note")),
+            Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
+            Pass(Command::Remove { item_type: ItemType::Vl, name: "vault_2".to_string() }),
+            Fail(Command::Enter { name: "vault_2".to_string() }),
+            Pass(Command::Trash { action: Some(TrashAction::Restore { item: "vault_2".to_string() }), vaults: true }),
+            Pass(Command::Enter { name: "vault_2".to_string() })
+        ];
+    }
+
+    #[test]
+    fn rename_note_keeps_extension() {
+        run_test(|| {
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(Command::Note { name: "note".to_string(), from_template: false, template_name: None, extension: None }),
+                Pass(Command::Rename { item_type: ItemType::Nt, name: "note".to_string(), new_name: "renamed".to_string() }),
+            ]);
+            assert!(test_vaults().join("vault_1/renamed.md").is_file());
+            assert!(!test_vaults().join("vault_1/renamed").exists());
+        });
+    }
+
+    #[test]
+    fn undo_operations() {
+        let note = |name: &str| Command::Note { name: name.to_string(), from_template: false, template_name: None, extension: None };
+        let open = |name: &str| Command::Open { name: name.to_string() };
+        let undo = |count: usize| Command::Undo { count };
+        run! [
+            Pass(note("note")),
+            Pass(Command::Rename { item_type: ItemType::Nt, name: "note".to_string(), new_name: "renamed".to_string() }),
+            Pass(Command::Folder { name: "folder".to_string() }),
+            Pass(Command::Move { item_type: ItemType::Nt, name: "renamed".to_string(), new_location: PathBuf::from("folder") }),
+            Fail(open("renamed")),
+            Pass(undo(2)), // undo the move and the folder creation
+            Pass(open("renamed")),
+            Fail(Command::Chdir { path: PathBuf::from("folder") }),
+            Pass(undo(1)),
+            Pass(open("note")),
+            Pass(Command::Remove { item_type: ItemType::Nt, name: "note".to_string() }),
+            Pass(undo(1)),
+            Pass(open("note")),
+            Pass(Command::Append { name: "note".to_string(), text: Some("text".to_string()), under: None, timestamp: false }),
+            Fail(undo(1)), // Err: note changed since it was created
+            Pass(open("note"))
+        ];
+    }
+
+    #[test]
+    fn history_logs_mutating_commands() {
+        run_test(|| {
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_2".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(Command::Note { name: "note".to_string(), from_template: false, template_name: None, extension: None }),
+                Pass(Command::Open { name: "note".to_string() }),
+                Pass(Command::Vmove { item_type: VaultItemType::Nt, name: "note".to_string(), vault_name: "vault_2".to_string() }),
+            ]);
+
+            let entries = History::entries().unwrap();
+            assert_eq!(4, entries.len()); // opening the note isn't logged
+            assert_eq!(Some("vault_1".to_string()), entries[3].vault);
+            assert!(entries[3].command.starts_with("Vmove"));
+            assert!(entries[3].paths[1].ends_with("vault_2/note.md"));
+
+            // the vmove changed vault_2, even though it was run from vault_1
+            let app = App::new().unwrap();
+            let Ok(Message::History(entries)) = app.history(&Some("vault_2".to_string()), &None) else {
+                panic!("expected a history message");
+            };
+            assert_eq!(2, entries.len());
+
+            // vault settings are logged with the vault's path
+            execute_commands(vec![
+                Pass(Command::Config { config_type: Some(ConfigType::NoteExtension), value: Some("txt".to_string()) }),
+            ]);
+            let entries = History::entries().unwrap();
+            assert!(entries[4].command.starts_with("Config"));
+            assert_eq!(vec![test_vaults().join("vault_1")], entries[4].paths);
+        });
+    }
+
+    #[test]
+    fn git_autocommit() {
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .arg("-C")
+                .arg(test_vaults().join("vault_1"))
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout).to_string()
+        };
+
+        run_test(|| {
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(Command::Note { name: "draft".to_string(), from_template: false, template_name: None, extension: None }),
+            ]);
+            git(&["init", "-q"]);
+            git(&["config", "user.name", "jeff"]);
+            git(&["config", "user.email", "jeff@example.com"]);
+
+            execute_commands(vec![
+                Pass(Command::Note { name: "ignored".to_string(), from_template: false, template_name: None, extension: None }), // autocommit is off
+                Pass(Command::Config { config_type: Some(ConfigType::GitAutocommit), value: Some("true".to_string()) }),
+                Pass(Command::Note { name: "note".to_string(), from_template: false, template_name: None, extension: None }),
+                Pass(Command::Rename { item_type: ItemType::Nt, name: "note".to_string(), new_name: "renamed".to_string() }),
+                Pass(Command::Append { name: "renamed".to_string(), text: Some("text".to_string()), under: None, timestamp: false }),
+            ]);
+
+            let log = git(&["log", "--format=%s"]);
+            assert_eq!("jf: append to note renamed\njf: rename note note -> renamed\njf: create note note\n", log);
+            // only the changed files are committed
+            assert!(git(&["status", "--porcelain"]).contains("?? draft.md"));
+
+            // uncommitted changes are committed before a restore, not snapshotted
+            execute_commands(vec![
+                Pass(Command::Config { config_type: Some(ConfigType::GitAutocommit), value: Some("false".to_string()) }),
+                Pass(Command::Append { name: "renamed".to_string(), text: Some("more".to_string()), under: None, timestamp: false }),
+            ]);
+            let mut app = App::new().unwrap();
+            let (note, revisions) = app.note_revisions(&"renamed".to_string()).unwrap();
+            let created = revisions.revisions().unwrap().pop().unwrap();
+            app.restore_note(&"renamed".to_string(), &created.id).unwrap();
+            assert_eq!("", std::fs::read_to_string(note.get_location().as_path()).unwrap());
+            assert!(git(&["log", "-1", "--format=%s"]).starts_with("jf: save note renamed before restoring it"));
+            assert!(!test_vaults().join("vault_1/.jeff/snapshots").exists());
+
+            // a failed commit doesn't fail the command, whose changes were made
+            std::fs::write(test_vaults().join("vault_1/.git/index.lock"), "").unwrap();
+            execute_commands(vec![
+                Pass(Command::Note { name: "uncommitted".to_string(), from_template: false, template_name: None, extension: None }),
+            ]);
+            assert!(test_vaults().join("vault_1/uncommitted.md").is_file());
+        });
+    }
+
+    #[test]
+    fn note_revisions_from_snapshots() {
+        run_test(|| {
+            let append = |text: &str| Pass(Command::Append { name: "note".to_string(), text: Some(text.to_string()), under: None, timestamp: false });
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Fail(Command::Log { name: "note".to_string() }),
+                Pass(Command::Note { name: "note".to_string(), from_template: false, template_name: None, extension: None }),
+                Pass(Command::Open { name: "note".to_string() }), // snapshot of the empty note
+                append("first"),
+                Pass(Command::Open { name: "note".to_string() }), // snapshot with "first"
+                append("second"),
+                Pass(Command::Log { name: "note".to_string() }),
+                Pass(Command::Diff { name: "note".to_string(), revision: None }),
+                Fail(Command::Diff { name: "note".to_string(), revision: Some("missing".to_string()) }),
+            ]);
+
+            // a repository above the vault isn't the vault's history
+            let git = |args: &[&str]| std::process::Command::new("git").arg("-C").arg(test_vaults()).args(args).output().unwrap();
+            assert!(git(&["init", "-q"]).status.success());
+            assert!(git(&["add", "vault_1/note.md"]).status.success());
+
+            let mut app = App::new().unwrap();
+            let (note, revisions) = app.note_revisions(&"note".to_string()).unwrap();
+            let all = revisions.revisions().unwrap();
+            assert_eq!(2, all.len());
+
+            app.restore_note(&"note".to_string(), &all[0].id).unwrap();
+            assert_eq!("first\n", std::fs::read_to_string(note.get_location().as_path()).unwrap());
+            // the contents that were restored over are kept
+            assert_eq!(3, revisions.revisions().unwrap().len());
+        });
+    }
+
+    #[test]
+    fn import_existing_folder_as_vault() {
+        let import = |name: &str, path: PathBuf| Command::Vault { action: Some(VaultAction::Import { name: name.to_string(), path }), show_loc: false, name: None, location: None };
+
+        run_test(|| {
+            let folder = test_vaults().join("Obsidian Vault");
+            std::fs::create_dir_all(folder.join(".obsidian/plugins")).unwrap();
+            std::fs::create_dir_all(folder.join("daily")).unwrap();
+            std::fs::write(folder.join("idea.md"), "an idea").unwrap();
+
+            execute_commands(vec![
+                Fail(import("notes", test_vaults().join("missing"))),
+                Pass(import("notes", folder.clone())),
+                Fail(import("notes", folder.clone())), // Err: duplicate name
+                Fail(import("daily", folder.join("daily"))), // Err: inside of notes
+                Fail(import("all", test_vaults())), // Err: contains notes
+                Fail(Command::Vault { action: None, show_loc: false, name: Some("nested".to_string()), location: Some(folder.clone()) }),
+                Pass(Command::Enter { name: "notes".to_string() }),
+                Pass(Command::Open { name: "idea".to_string() }),
+                Pass(Command::Chdir { path: PathBuf::from("daily") }),
+                Fail(Command::Chdir { path: PathBuf::from("../.obsidian") }),
+            ]);
+
+            assert!(folder.join(".jeff/data").is_file());
+            let app = App::new().unwrap();
+            let Ok(Message::List(vault_name, _, _)) = app.list() else {
+                panic!("expected a list message");
+            };
+            assert_eq!("notes", vault_name);
+        });
+    }
+
+    #[test]
+    fn ignored_items_are_not_part_of_the_vault() {
+        run_test(|| {
+            let vault_path = test_vaults().join("vault_1");
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+            ]);
+            for folder in ["node_modules/pkg", ".github", "drafts"] {
+                std::fs::create_dir_all(vault_path.join(folder)).unwrap();
+            }
+            std::fs::write(vault_path.join("node_modules/pkg/readme.md"), "").unwrap();
+            std::fs::write(vault_path.join(".github/notes.md"), "").unwrap();
+            std::fs::write(vault_path.join("drafts/draft.tmp.md"), "").unwrap();
+            std::fs::write(vault_path.join(".jeffignore"), "node_modules/\n*.tmp.md\n!.github/\n").unwrap();
+
+            execute_commands(vec![
+                Fail(Command::Chdir { path: PathBuf::from("node_modules") }),
+                Pass(Command::Chdir { path: PathBuf::from(".github") }),
+                Pass(Command::Open { name: "notes".to_string() }),
+                Pass(Command::Chdir { path: PathBuf::from("../drafts") }),
+                Fail(Command::Open { name: "draft.tmp".to_string() }),
+            ]);
+
+            let app = App::new().unwrap();
+            let Ok(Message::List(_, _, tree)) = app.list() else {
+                panic!("expected a list message");
+            };
+            assert!(tree.is_empty());
+        });
+    }
+
+    #[test]
+    fn note_extensions() {
+        let note = |name: &str, extension: Option<&str>| Command::Note { name: name.to_string(), from_template: false, template_name: None, extension: extension.map(str::to_string) };
+        let config = |config_type: ConfigType, value: &str| Command::Config { config_type: Some(config_type), value: Some(value.to_string()) };
+        let open = |name: &str| Command::Open { name: name.to_string() };
+
+        run![
+            Fail(note("todo", Some("txt"))), // Err: txt isn't recognised yet
+            Fail(config(ConfigType::NoteExtensions, "md,rst")), // Err: unsupported extension
+            Pass(config(ConfigType::NoteExtensions, "md,.txt,org")),
+            Pass(note("todo", Some("txt"))),
+            Pass(note("todo", None)),
+            Fail(note("todo.md", None)), // Err: already exists
+            Fail(open("todo")), // Err: todo.md and todo.txt share a name
+            Pass(open("todo.txt")),
+            Pass(config(ConfigType::NoteExtension, "org")),
+            Pass(note("agenda", None)),
+            Pass(open("agenda")),
+            Pass(Command::Rename { item_type: ItemType::Nt, name: "agenda".to_string(), new_name: "plan".to_string() }),
+            Pass(open("plan.org")),
+            Pass(config(ConfigType::NoteExtensions, "md")),
+            Fail(open("plan")), // Err: org notes are no longer recognised
+            Pass(open("todo"))
+        ];
+    }
+
+    #[test]
+    fn relink_missing_vault() {
+        let relink = |name: &str, path: PathBuf| Command::Vault { action: Some(VaultAction::Relink { name: name.to_string(), path }), show_loc: false, name: None, location: None };
+        let note = || Command::Note { name: "note_1".to_string(), from_template: false, template_name: None, extension: None };
+
+        run_test(|| {
+            let (vault, moved) = (test_vaults().join("vault_1"), test_vaults().join("moved"));
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+            ]);
+            std::fs::rename(&vault, &moved).unwrap();
+
+            let app = App::new().unwrap();
+            assert!(matches!(app.vaults.missing_current(), Some(Error::VaultPathMissing(..))));
+            assert!(app.vaults.ref_current().is_err());
+
+            execute_commands(vec![
+                Fail(note()), // Err: not inside a vault
+                Fail(relink("vault_1", test_vaults().join("gone"))),
+                Fail(relink("vault_2", moved.clone())),
+                Pass(relink("vault_1", moved.clone())),
+                Pass(note()),
+            ]);
+            assert!(moved.join("note_1.md").is_file());
+        });
+    }
+
+    #[test]
+    fn archive_and_restore_vault() {
+        let action = |action: VaultAction| Command::Vault { action: Some(action), show_loc: false, name: None, location: None };
+        let archive = |name: &str, archive: PathBuf| action(VaultAction::Archive { name: name.to_string(), archive });
+        let restore = |archive: PathBuf, name: Option<&str>, location: PathBuf| action(VaultAction::Restore { archive, name: name.map(str::to_string), location: Some(location) });
+
+        run_test(|| {
+            let (backups, restored) = (test_vaults().join("backups"), test_vaults().join("restored"));
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(Command::Note { name: "note_1".to_string(), from_template: false, template_name: None, extension: None }),
+                Fail(archive("vault_1", test_vaults().join("vault_1/backup.zip"))), // Err: inside of the vault
+                Fail(archive("vault_1", backups.join("vault_1.rar"))), // Err: unsupported format
+                Pass(archive("vault_1", backups.join("vault_1.tar.gz"))),
+                Pass(archive("vault_1", backups.join("vault_1.zip"))),
+                Fail(restore(backups.join("vault_1.zip"), None, restored.clone())), // Err: vault_1 is registered
+                Pass(restore(backups.join("vault_1.zip"), Some("vault_2"), restored.clone())),
+                Pass(restore(backups.join("vault_1.tar.gz"), Some("vault_3"), restored.clone())),
+                Pass(Command::Enter { name: "vault_2".to_string() }),
+                Pass(Command::Open { name: "note_1".to_string() }),
+            ]);
+
+            assert!(restored.join("vault_3/note_1.md").is_file());
+            assert!(restored.join("vault_3/.jeff/data").is_file());
+
+            // archives are checked even when the vault is named
+            let loose = backups.join("loose.zip");
+            let mut writer = zip::ZipWriter::new(std::fs::File::create(&loose).unwrap());
+            for name in ["a/note.md", "b/note.md"] {
+                writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            }
+            writer.finish().unwrap();
+            execute_commands(vec![
+                Fail(restore(loose, Some("vault_4"), restored.clone())), // Err: more than one folder
+            ]);
+            assert!(!restored.join("vault_4").exists());
+        });
+    }
+
+    #[test]
+    fn obsidian_settings() {
+        run_test(|| {
+            let folder = test_vaults().join("Obsidian Vault");
+            let settings = folder.join(".obsidian");
+            std::fs::create_dir_all(&settings).unwrap();
+            std::fs::create_dir_all(folder.join("Templates")).unwrap();
+            std::fs::write(folder.join("Templates/Daily.md"), "# {{title}}").unwrap();
+            std::fs::write(folder.join("Templates/Meeting.md"), "# {{title}} {{date:YYYY}}").unwrap();
+            std::fs::write(settings.join("daily-notes.json"), r#"{"format": "YYYY/MM/DD", "folder": "Journal", "template": "Templates/Daily"}"#).unwrap();
+            std::fs::write(settings.join("templates.json"), r#"{"folder": "Templates"}"#).unwrap();
+            std::fs::write(settings.join("app.json"), r#"{"newFileLocation": "folder", "newFileFolderPath": "Inbox"}"#).unwrap();
+
+            execute_commands(vec![
+                Pass(Command::Vault { action: Some(VaultAction::Import { name: "notes".to_string(), path: folder.clone() }), show_loc: false, name: None, location: None }),
+                Pass(Command::Enter { name: "notes".to_string() }),
+                Pass(Command::Today),
+                Pass(Command::Note { name: "standup".to_string(), from_template: true, template_name: Some("Meeting".to_string()), extension: None }),
+                Fail(Command::Note { name: "standup".to_string(), from_template: false, template_name: None, extension: None }),
+            ]);
+
+            let now = Local::now();
+            let daily_note = folder.join(now.format("Journal/%Y/%m/%d.md").to_string());
+            assert_eq!(now.format("# %d").to_string(), std::fs::read_to_string(daily_note).unwrap());
+            assert_eq!(
+                now.format("# standup %Y").to_string(),
+                std::fs::read_to_string(folder.join("Inbox/standup.md")).unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn attachments() {
+        run_test(|| {
+            let vault = test_vaults().join("vault_1");
+            let photo = test_vaults().join("photo.png");
+            std::fs::create_dir_all(test_vaults()).unwrap();
+            std::fs::write(&photo, "photo").unwrap();
+            let note = |name: &str| Command::Note { name: name.to_string(), from_template: false, template_name: None, extension: None };
+            let attach = |name: &str, link: Option<LinkStyle>| Command::Attach { name: name.to_string(), file: photo.clone(), link };
+
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(note("first")),
+                Pass(note("second")),
+                Fail(attach("third", None)), // Err: note doesn't exist
+                Pass(attach("first", None)),
+                Pass(attach("second", Some(LinkStyle::Wiki))), // same contents, not copied again
+            ]);
+
+            let attachments = std::fs::read_dir(vault.join("attachments")).unwrap().count();
+            assert_eq!(1, attachments);
+            let first = std::fs::read_to_string(vault.join("first.md")).unwrap();
+            assert!(first.contains("![photo.png](attachments/photo.png)"));
+            let second = std::fs::read_to_string(vault.join("second.md")).unwrap();
+            assert!(second.contains("![[photo.png]]"));
+
+            std::fs::write(vault.join("attachments/unused.pdf"), "unused").unwrap();
+            std::fs::write(vault.join("second.md"), "![[gone.png]]").unwrap();
+            let app = App::new().unwrap();
+            let Message::Attachments(orphans) = app.attachments(true, false).unwrap() else { panic!() };
+            assert_eq!(vec![PathBuf::from("attachments/unused.pdf")], orphans);
+            let Message::MissingEmbeds(missing) = app.attachments(false, true).unwrap() else { panic!() };
+            assert_eq!(1, missing.len());
+            assert_eq!("gone.png", missing[0].target);
+        });
+    }
+
+    #[test]
+    fn doctor() {
+        run_test(|| {
+            let vault = |name: &str| Command::Vault { action: None, show_loc: false, name: Some(name.to_string()), location: Some(test_vaults()) };
+            execute_commands(vec![
+                Pass(vault("vault_1")),
+                Pass(vault("vault_2")),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(Command::Folder { name: "folder_1".to_string() }),
+                Pass(Command::Chdir { path: PathBuf::from("folder_1") }),
+            ]);
+            std::fs::remove_dir_all(test_vaults().join("vault_1/folder_1")).unwrap();
+            std::fs::remove_dir_all(test_vaults().join("vault_2")).unwrap();
+
+            let checks = |fix: bool| {
+                let Message::Doctor(problems) = App::new().unwrap().doctor(fix).unwrap() else { panic!() };
+                problems.iter().map(|problem| (problem.check, problem.fixed)).collect::<Vec<(Check, bool)>>()
+            };
+            assert_eq!(vec![(Check::StaleFolder, false), (Check::MissingVault, false)], checks(false));
+            assert_eq!(vec![(Check::StaleFolder, true), (Check::MissingVault, false)], checks(true));
+            assert_eq!(vec![(Check::MissingVault, false)], checks(false));
+
+            execute_commands(vec![
+                Pass(Command::Remove { item_type: ItemType::Vl, name: "vault_2".to_string() }),
+                Fail(Command::Enter { name: "vault_2".to_string() }),
+            ]);
+            assert!(checks(false).is_empty());
+
+            execute_commands(vec![Pass(Command::Remove { item_type: ItemType::Vl, name: "vault_1".to_string() })]);
+            assert!(matches!(App::new().unwrap().doctor(false), Err(Error::NotInsideVault)));
+        });
+    }
+
+    #[test]
+    fn markdown_format_only_for_bundle() {
+        use clap::Parser;
+
+        let check = |words: &[&str]| {
+            let args = crate::state::Args::try_parse_from(words).unwrap();
+            args.command.check_format(args.format)
+        };
+        assert!(check(&["jf", "--format", "md", "export", "bundle", "notes"]).is_ok());
+        assert!(check(&["jf", "export", "bundle", "notes", "--format", "json"]).is_ok());
+        assert!(matches!(check(&["jf", "--format", "md", "list"]), Err(Error::UnsupportedFormat(_))));
+        assert!(check(&["jf", "--format", "json", "list"]).is_ok());
+    }
+
+    #[test]
+    fn export_bundle() {
+        let bundle = |name: &str, inline_links: bool| Command::Export { action: ExportAction::Bundle { name: name.to_string(), inline_links } };
+        let note = |name: &str| Command::Note { name: name.to_string(), from_template: false, template_name: None, extension: None };
+
+        run_test(|| {
+            execute_commands(vec![
+                Pass(Command::Vault { action: None, show_loc: false, name: Some("vault_1".to_string()), location: Some(test_vaults()) }),
+                Pass(Command::Enter { name: "vault_1".to_string() }),
+                Pass(Command::Folder { name: "folder_1".to_string() }),
+                Pass(Command::Chdir { path: PathBuf::from("folder_1") }),
+                Pass(note("b_note")),
+                Pass(Command::Folder { name: "nested".to_string() }),
+                Pass(Command::Chdir { path: PathBuf::from("nested") }),
+                Pass(note("a_note")),
+                Pass(Command::Chdir { path: PathBuf::from("../..") }),
+                Pass(note("note_1")),
+                Fail(bundle("note_3", false)), // Err: no such note or folder
+            ]);
+            std::fs::write(test_vaults().join("vault_1/folder_1/b_note.md"), "See [[a_note]] and [[note_1]].").unwrap();
+
+            let mut app = App::new().unwrap();
+            let mut markdown = |name: &str, inline_links: bool| match app.handle_command(bundle(name, inline_links)) {
+                Ok(Message::Bundle(bundle)) => bundle.to_markdown(),
+                _ => panic!("expected a bundle"),
+            };
+            assert_eq!("# note_1\n\n", markdown("note_1", false));
+            // in the order of `list`: folders, then notes
+            assert_eq!(
+                "# folder_1/nested/a_note\n\n\n\n# folder_1/b_note\n\nSee [[a_note]] and [[note_1]].",
+                markdown("folder_1", false)
+            );
+            assert_eq!(
+                "# folder_1/nested/a_note\n\n\n\n# folder_1/b_note\n\nSee [a_note](#folder_1nesteda_note) and note_1.",
+                markdown("folder_1", true)
+            );
+        });
+    }
+
     #[test]
     fn create_and_edit_and_list_templates() {
         run! [
@@ -613,10 +2142,10 @@ mod test {
     #[test]
     fn create_note_from_template() {
         run! [
-            Fail(Command::Note { name: "note_1".to_string(), from_template: true, template_name: None }), // no template name
-            Fail(Command::Note { name: "note_1".to_string(), from_template: true, template_name: Some("template".to_string())}), // template does not exist
+            Fail(Command::Note { name: "note_1".to_string(), from_template: true, template_name: None, extension: None }), // no template name
+            Fail(Command::Note { name: "note_1".to_string(), from_template: true, template_name: Some("template".to_string()), extension: None }), // template does not exist
             Pass(Command::Template { name: Some("template".to_string()) }), // create template 
-            Pass(Command::Note { name: "note_1".to_string(), from_template: true, template_name: Some("template".to_string())}), // template does not exist
+            Pass(Command::Note { name: "note_1".to_string(), from_template: true, template_name: Some("template".to_string()), extension: None }), // template does not exist
             Pass(Command::Open { name: "note_1".to_string() })
         ];
     }
@@ -0,0 +1,242 @@
+/**
+ * Archives of vaults, as `.tar.gz` or `.zip` files, for backing vaults up
+ * and moving them between machines.
+ */
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::fs::{create_dir_all, read_dir, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn of(archive: &Path) -> JeffResult<Self> {
+        let name = archive
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(ArchiveFormat::TarGz)
+        } else if name.ends_with(".zip") {
+            Ok(ArchiveFormat::Zip)
+        } else {
+            Err(Error::UnsupportedArchive(archive.to_path_buf()))
+        }
+    }
+}
+
+/**
+ * Writes everything inside of the vault at `vault_path`, including its
+ * `.jeff` folder, to `archive`. The files are put inside of a folder named
+ * after the vault's folder. Symbolic links are stored as links in `.tar.gz`
+ * archives, and left out of `.zip` archives, rather than followed.
+ */
+pub fn create(vault_path: &Path, archive: &Path) -> JeffResult<()> {
+    let format = ArchiveFormat::of(archive)?;
+    let root = PathBuf::from(vault_path.file_name().unwrap_or_default());
+    if let Some(parent) = archive.parent() {
+        create_dir_all(parent)?;
+    }
+    let file = File::create(archive)?;
+
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            builder.follow_symlinks(false);
+            builder.append_dir_all(&root, vault_path)?;
+            builder.into_inner()?.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let mut writer = ZipWriter::new(file);
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+            add_to_zip(&mut writer, options, vault_path, &root)?;
+            writer.finish().map_err(|error| zip_error(archive, error))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the contents of `folder` to a zip archive, as `name`.
+fn add_to_zip(
+    writer: &mut ZipWriter<File>,
+    options: SimpleFileOptions,
+    folder: &Path,
+    name: &Path,
+) -> JeffResult<()> {
+    let entry_name = |path: &Path| path_to_string(path.to_path_buf()).replace('\\', "/");
+    writer
+        .add_directory(entry_name(name), options)
+        .map_err(|error| zip_error(folder, error))?;
+
+    let mut entries: Vec<PathBuf> = read_dir(folder)?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = name.join(path.file_name().unwrap());
+        if path.is_symlink() {
+            continue;
+        } else if path.is_dir() {
+            add_to_zip(writer, options, &path, &name)?;
+        } else {
+            writer
+                .start_file(entry_name(&name), options)
+                .map_err(|error| zip_error(&path, error))?;
+            io::copy(&mut File::open(&path)?, writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the name of the folder that everything in `archive` is inside of.
+pub fn root_name(archive: &Path) -> JeffResult<String> {
+    let mut root: Option<String> = None;
+    for path in entry_paths(archive)? {
+        let Some(Component::Normal(name)) = path.components().next() else {
+            return Err(Error::InvalidArchive(archive.to_path_buf()));
+        };
+        let name = name.to_string_lossy().to_string();
+        match &root {
+            Some(root) if *root != name => {
+                return Err(Error::InvalidArchive(archive.to_path_buf()))
+            }
+            Some(_) => {}
+            None => root = Some(name),
+        }
+    }
+
+    root.ok_or_else(|| Error::InvalidArchive(archive.to_path_buf()))
+}
+
+/// Returns the paths of the entries of `archive`.
+fn entry_paths(archive: &Path) -> JeffResult<Vec<PathBuf>> {
+    match ArchiveFormat::of(archive)? {
+        ArchiveFormat::TarGz => {
+            let mut tar = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+            let mut paths = vec![];
+            for entry in tar.entries()? {
+                paths.push(entry?.path()?.to_path_buf());
+            }
+
+            Ok(paths)
+        }
+        ArchiveFormat::Zip => {
+            let zip =
+                ZipArchive::new(File::open(archive)?).map_err(|error| zip_error(archive, error))?;
+
+            Ok(zip.file_names().map(PathBuf::from).collect())
+        }
+    }
+}
+
+/**
+ * Extracts `archive` to `destination`, leaving out the folder that
+ * everything in it is inside of. Only files and folders are extracted, and
+ * entries that would end up outside of `destination` are refused.
+ */
+pub fn extract(archive: &Path, destination: &Path) -> JeffResult<()> {
+    let target = |path: &Path| -> JeffResult<PathBuf> {
+        let mut components = path.components();
+        components.next();
+        let relative = components.as_path();
+        let is_safe = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !is_safe {
+            return Err(Error::InvalidArchive(archive.to_path_buf()));
+        }
+
+        Ok(destination.join(relative))
+    };
+    create_dir_all(destination)?;
+
+    match ArchiveFormat::of(archive)? {
+        ArchiveFormat::TarGz => {
+            let mut tar = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                let path = target(&entry.path()?)?;
+                let entry_type = entry.header().entry_type();
+                if entry_type.is_dir() {
+                    create_dir_all(&path)?;
+                } else if entry_type.is_file() {
+                    create_dir_all(path.parent().unwrap())?;
+                    entry.unpack(&path)?;
+                }
+            }
+        }
+        ArchiveFormat::Zip => {
+            let mut zip =
+                ZipArchive::new(File::open(archive)?).map_err(|error| zip_error(archive, error))?;
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i).map_err(|error| zip_error(archive, error))?;
+                let path = target(&PathBuf::from(file.name()))?;
+                if file.is_dir() {
+                    create_dir_all(&path)?;
+                } else if file.is_file() {
+                    create_dir_all(path.parent().unwrap())?;
+                    io::copy(&mut file, &mut File::create(&path)?)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn zip_error(archive: &Path, error: ZipError) -> Error {
+    match error {
+        ZipError::Io(error) => Error::Io(error),
+        _ => Error::InvalidArchive(archive.to_path_buf()),
+    }
+}
+
+#[test]
+fn archive_tests() {
+    let folder = std::env::temp_dir().join(format!("jeff_archive_{}", std::process::id()));
+    let vault = folder.join("notes");
+    std::fs::create_dir_all(vault.join(".jeff")).unwrap();
+    std::fs::create_dir_all(vault.join("projects")).unwrap();
+    std::fs::write(vault.join(".jeff/data"), "current_folder = 'projects'").unwrap();
+    std::fs::write(vault.join("projects/roadmap.md"), "Ship it.").unwrap();
+    // links aren't followed out of the vault
+    std::fs::create_dir_all(folder.join("outside")).unwrap();
+    std::fs::write(folder.join("outside/secret.md"), "secret").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(folder.join("outside"), vault.join("linked")).unwrap();
+
+    for name in ["notes.tar.gz", "notes.zip"] {
+        let archive = folder.join(name);
+        create(&vault, &archive).unwrap();
+        assert_eq!("notes", root_name(&archive).unwrap());
+
+        let restored = folder.join(format!("restored-{}", name));
+        extract(&archive, &restored).unwrap();
+        assert_eq!(
+            "Ship it.",
+            std::fs::read_to_string(restored.join("projects/roadmap.md")).unwrap()
+        );
+        assert!(restored.join(".jeff/data").is_file());
+        assert!(!restored.join("linked").exists());
+        assert!(!entry_paths(&archive)
+            .unwrap()
+            .iter()
+            .any(|path| path.ends_with("secret.md")));
+    }
+
+    assert!(create(&vault, &folder.join("notes.rar")).is_err());
+
+    std::fs::remove_dir_all(&folder).unwrap();
+}
@@ -0,0 +1,292 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{copy, create_dir_all, read, read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use crate::enums::LinkStyle;
+use crate::ignore::JeffIgnore;
+use crate::markdown;
+use crate::obsidian::Obsidian;
+use crate::prelude::*;
+
+/// Returns the folder that attachments of notes in `note_folder` are copied
+/// to: the attachment folder of Obsidian vaults, or `<vault>/attachments`.
+pub fn attachment_folder(vault_path: &Path, note_folder: &Path) -> JeffResult<PathBuf> {
+    Ok(match Obsidian::load(vault_path)? {
+        Some(obsidian) => obsidian.attachment_folder(note_folder),
+        None => vault_path.join("attachments"),
+    })
+}
+
+/// Returns the style of the links that jeff writes: the one configured in
+/// Obsidian vaults (wiki links by default), or markdown links.
+pub fn link_style(vault_path: &Path) -> JeffResult<LinkStyle> {
+    Ok(match Obsidian::load(vault_path)? {
+        Some(obsidian) if !obsidian.uses_markdown_links() => LinkStyle::Wiki,
+        _ => LinkStyle::Markdown,
+    })
+}
+
+/// Returns the SHA-256 hash of a file's contents.
+pub fn hash_file(path: &Path) -> JeffResult<String> {
+    let digest = Sha256::digest(read(path)?);
+
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/**
+ * Copies `file` into `folder`, unless a file with the same contents is
+ * already there. Files with the same name but other contents get the start
+ * of their hash added to their name. Returns the path of the attachment, and
+ * whether it was copied.
+ */
+pub fn store(file: &Path, folder: &Path) -> JeffResult<(PathBuf, bool)> {
+    let hash = hash_file(file)?;
+    let size = file.metadata()?.len();
+
+    if folder.is_dir() {
+        for entry in read_dir(folder)?.flatten() {
+            let path = entry.path();
+            let same_size = entry
+                .metadata()
+                .is_ok_and(|data| data.is_file() && data.len() == size);
+            if same_size && hash_file(&path)? == hash {
+                return Ok((path, false));
+            }
+        }
+    }
+
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| Error::InvalidPath(file.to_path_buf()))?;
+    let mut path = folder.join(file_name);
+    if path.exists() {
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match file.extension() {
+            Some(extension) => format!("{}-{}.{}", stem, &hash[..8], extension.to_string_lossy()),
+            None => format!("{}-{}", stem, &hash[..8]),
+        };
+        path = folder.join(name);
+    }
+
+    create_dir_all(folder)?;
+    copy(file, &path)?;
+
+    Ok((path, true))
+}
+
+/// A link whose file doesn't exist.
+#[derive(Debug, Clone)]
+pub struct MissingLink {
+    /// path to the note, relative to the vault
+    pub note: PathBuf,
+    pub target: String,
+}
+
+/**
+ * The notes and attachments of a vault. Attachments are the files that
+ * aren't notes, leaving out hidden and ignored files.
+ */
+pub struct VaultFiles {
+    root: PathBuf,
+    notes: Vec<PathBuf>,
+    attachments: Vec<PathBuf>,
+    /// lowercase file names, for resolving links by name
+    by_name: HashMap<String, Vec<PathBuf>>,
+}
+
+impl VaultFiles {
+    pub fn load(vault_path: &Path) -> JeffResult<Self> {
+        fn visit(
+            folder: &Path,
+            ignore: &JeffIgnore,
+            notes: &mut Vec<PathBuf>,
+            attachments: &mut Vec<PathBuf>,
+        ) -> JeffResult<()> {
+            for entry in read_dir(folder)?.flatten() {
+                let path = entry.path();
+                let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if is_hidden || ignore.is_ignored(&path) {
+                    continue;
+                }
+
+                if path.is_dir() {
+                    visit(&path, ignore, notes, attachments)?;
+                } else if ignore.is_note(&path) {
+                    notes.push(path);
+                } else {
+                    attachments.push(path);
+                }
+            }
+
+            Ok(())
+        }
+
+        let ignore = JeffIgnore::load(vault_path)?;
+        let (mut notes, mut attachments) = (vec![], vec![]);
+        visit(vault_path, &ignore, &mut notes, &mut attachments)?;
+        notes.sort();
+        attachments.sort();
+
+        let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for path in notes.iter().chain(attachments.iter()) {
+            let name = path.file_name().unwrap().to_string_lossy().to_lowercase();
+            by_name.entry(name).or_default().push(path.to_owned());
+        }
+
+        Ok(VaultFiles {
+            root: vault_path.to_path_buf(),
+            notes,
+            attachments,
+            by_name,
+        })
+    }
+
+    pub fn notes(&self) -> &[PathBuf] {
+        &self.notes
+    }
+
+    pub fn attachments(&self) -> &[PathBuf] {
+        &self.attachments
+    }
+
+    /**
+     * Returns the file that a link in `note` points to. Like in Obsidian,
+     * wiki links name a file anywhere in the vault (optionally with part of
+     * its path), and links to notes can leave out the note's extension.
+     * Markdown links are relative to the note, or to the vault.
+     */
+    pub fn resolve(&self, note: &Path, link: &Link) -> Option<PathBuf> {
+        if !link.wiki {
+            let folder = note.parent().unwrap_or(&self.root);
+            let candidates = match link.target.strip_prefix('/') {
+                Some(target) => vec![self.root.join(target)],
+                None => vec![folder.join(&link.target), self.root.join(&link.target)],
+            };
+            for candidate in candidates {
+                let candidate = process_path(&candidate);
+                if candidate.starts_with(&self.root) && candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+
+        let target = link.target.trim_start_matches('/').to_lowercase();
+        let find = |target: &str| {
+            let name = target.rsplit('/').next().unwrap_or(target);
+            self.by_name.get(name)?.iter().find_map(|path| {
+                let relative = path.strip_prefix(&self.root).ok()?;
+                let relative = path_to_string(relative.to_path_buf()).to_lowercase();
+                let matches = relative == target || relative.ends_with(&format!("/{}", target));
+                matches.then(|| path.to_owned())
+            })
+        };
+
+        find(&target).or_else(|| {
+            NOTE_EXTENSIONS
+                .iter()
+                .find_map(|extension| find(&format!("{}.{}", target, extension)))
+        })
+    }
+
+    /// Returns the links of every note, along with the note.
+    pub fn links(&self) -> JeffResult<Vec<(PathBuf, Link)>> {
+        let mut links = vec![];
+        for note in &self.notes {
+            for link in markdown::links(&read_to_string(note)?) {
+                links.push((note.to_owned(), link));
+            }
+        }
+
+        Ok(links)
+    }
+
+    /// Returns the attachments that no note links to.
+    pub fn orphans(&self) -> JeffResult<Vec<PathBuf>> {
+        let linked: Vec<PathBuf> = self
+            .links()?
+            .iter()
+            .filter_map(|(note, link)| self.resolve(note, link))
+            .collect();
+
+        Ok(self
+            .attachments
+            .iter()
+            .filter(|attachment| !linked.contains(attachment))
+            .map(|attachment| self.relative(attachment))
+            .collect())
+    }
+
+    /// Returns the embeds whose file doesn't exist.
+    pub fn missing(&self) -> JeffResult<Vec<MissingLink>> {
+        Ok(self
+            .links()?
+            .into_iter()
+            .filter(|(note, link)| link.embed && self.resolve(note, link).is_none())
+            .map(|(note, link)| MissingLink {
+                note: self.relative(&note),
+                target: link.target,
+            })
+            .collect())
+    }
+
+    /// Returns the embed for `attachment` in `note`. Wiki links use the
+    /// attachment's name, unless another file has the same name.
+    pub fn embed(&self, note: &Path, attachment: &Path, style: LinkStyle) -> String {
+        let name = attachment
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        match style {
+            LinkStyle::Wiki => {
+                let same_name = self
+                    .by_name
+                    .get(&name.to_lowercase())
+                    .is_some_and(|paths| paths.iter().any(|path| path != attachment));
+                if same_name {
+                    format!("![[{}]]", path_to_string(self.relative(attachment)))
+                } else {
+                    format!("![[{}]]", name)
+                }
+            }
+            LinkStyle::Markdown => {
+                let folder = note.parent().unwrap_or(&self.root);
+                let path = path_to_string(relative_path(folder, attachment));
+                format!("![{}]({})", name, markdown::percent_encode(&path))
+            }
+        }
+    }
+
+    /// Returns the path of a file of the vault, relative to the vault.
+    pub fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).unwrap_or(path).to_path_buf()
+    }
+}
+
+#[test]
+fn store_tests() {
+    let folder = std::env::temp_dir().join(format!("jeff_attachments_{}", std::process::id()));
+    let (first, second) = (folder.join("a/photo.png"), folder.join("b/photo.png"));
+    create_dir_all(first.parent().unwrap()).unwrap();
+    create_dir_all(second.parent().unwrap()).unwrap();
+    std::fs::write(&first, "first").unwrap();
+    std::fs::write(&second, "second").unwrap();
+    let attachments = folder.join("attachments");
+
+    let (path, copied) = store(&first, &attachments).unwrap();
+    assert_eq!((attachments.join("photo.png"), true), (path, copied));
+    // same contents
+    let (path, copied) = store(&first, &attachments).unwrap();
+    assert_eq!((attachments.join("photo.png"), false), (path, copied));
+    // same name, other contents
+    let (path, copied) = store(&second, &attachments).unwrap();
+    let hash = hash_file(&second).unwrap();
+    assert_eq!(
+        (attachments.join(format!("photo-{}.png", &hash[..8])), true),
+        (path, copied)
+    );
+
+    std::fs::remove_dir_all(&folder).unwrap();
+}
@@ -0,0 +1,200 @@
+/**
+ * Checks that audit a vault, and the registry of vaults, for problems.
+ */
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::attachments::VaultFiles;
+use crate::ignore::JeffIgnore;
+use crate::prelude::*;
+
+/// The kinds of problems that `jf doctor` looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// a wiki or markdown link to a file that doesn't exist
+    BrokenLink,
+    /// a note that no other note links to
+    OrphanNote,
+    /// notes in different folders with the same name
+    DuplicateName,
+    EmptyNote,
+    /// a file or folder name with characters that aren't allowed in names
+    InvalidName,
+    /// a current folder that no longer exists
+    StaleFolder,
+    /// a registered vault whose folder doesn't exist
+    MissingVault,
+}
+
+impl Check {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::BrokenLink => "broken_link",
+            Check::OrphanNote => "orphan_note",
+            Check::DuplicateName => "duplicate_name",
+            Check::EmptyNote => "empty_note",
+            Check::InvalidName => "invalid_name",
+            Check::StaleFolder => "stale_folder",
+            Check::MissingVault => "missing_vault",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Check::BrokenLink => "broken link",
+            Check::OrphanNote => "orphan note",
+            Check::DuplicateName => "duplicate name",
+            Check::EmptyNote => "empty note",
+            Check::InvalidName => "invalid name",
+            Check::StaleFolder => "stale folder",
+            Check::MissingVault => "missing vault",
+        }
+    }
+}
+
+/// A problem found by [Check]s.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub check: Check,
+    /// the path, relative to the vault, or vault name the problem is about
+    pub subject: String,
+    pub detail: Option<String>,
+    /// whether `--fix` fixed the problem
+    pub fixed: bool,
+}
+
+impl Problem {
+    fn new(check: Check, subject: String, detail: Option<String>) -> Self {
+        Problem {
+            check,
+            subject,
+            detail,
+            fixed: false,
+        }
+    }
+}
+
+/// Checks the notes, attachments and folders of the vault at `vault_path`.
+pub fn check_vault(vault_path: &Path) -> JeffResult<Vec<Problem>> {
+    let files = VaultFiles::load(vault_path)?;
+    let relative = |path: &Path| path_to_string(files.relative(path));
+    let mut problems = vec![];
+
+    let mut linked = BTreeSet::new();
+    for (note, link) in files.links()? {
+        match files.resolve(&note, &link) {
+            Some(target) if target != note => {
+                linked.insert(target);
+            }
+            Some(_) => {}
+            None => problems.push(Problem::new(
+                Check::BrokenLink,
+                relative(&note),
+                Some(link.target),
+            )),
+        }
+    }
+
+    let mut by_name: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+    for note in files.notes() {
+        if !linked.contains(note) {
+            problems.push(Problem::new(Check::OrphanNote, relative(note), None));
+        }
+        if read_to_string(note)?.trim().is_empty() {
+            problems.push(Problem::new(Check::EmptyNote, relative(note), None));
+        }
+
+        let name = note.file_stem().unwrap_or_default().to_string_lossy();
+        by_name.entry(name.to_lowercase()).or_default().push(note);
+    }
+
+    for (name, notes) in by_name {
+        if notes.len() > 1 {
+            let paths: Vec<String> = notes.iter().map(|note| relative(note)).collect();
+            problems.push(Problem::new(
+                Check::DuplicateName,
+                name,
+                Some(paths.join(", ")),
+            ));
+        }
+    }
+
+    // folders are checked through the files inside of them
+    let mut paths = BTreeSet::new();
+    for file in files.notes().iter().chain(files.attachments()) {
+        let mut path = PathBuf::new();
+        for component in files.relative(file).iter() {
+            path.push(component);
+            paths.insert(path.clone());
+        }
+    }
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !valid_name(&name) || name.trim() != name {
+            problems.push(Problem::new(Check::InvalidName, path_to_string(path), None));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Checks that `folder`, the current folder of the vault `name`, still
+/// exists and isn't ignored.
+pub fn check_current_folder(name: &str, vault_path: &Path, folder: &str) -> Option<Problem> {
+    let path = vault_path.join(folder);
+    let is_ignored = JeffIgnore::load(vault_path).is_ok_and(|ignore| ignore.is_ignored(&path));
+
+    (!path.is_dir() || is_ignored)
+        .then(|| Problem::new(Check::StaleFolder, name.to_owned(), Some(folder.to_owned())))
+}
+
+/// Checks that every registered vault still exists.
+pub fn check_registry(vaults: &[VaultInfo]) -> Vec<Problem> {
+    vaults
+        .iter()
+        .filter(|vault| !vault.path.join(".jeff").is_dir())
+        .map(|vault| {
+            Problem::new(
+                Check::MissingVault,
+                vault.name.to_owned(),
+                Some(format!(
+                    "{} (use jf vault relink if it moved, or jf rm vl)",
+                    path_to_string(vault.path.to_owned())
+                )),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn check_vault_tests() {
+    let vault = std::env::temp_dir().join(format!("jeff_doctor_{}", std::process::id()));
+    std::fs::create_dir_all(vault.join(".jeff")).unwrap();
+    std::fs::create_dir_all(vault.join("a")).unwrap();
+    std::fs::create_dir_all(vault.join("b:c")).unwrap();
+    std::fs::write(vault.join("index.md"), "[[plan]] [x](a/todo.md) [[gone]]").unwrap();
+    std::fs::write(vault.join("plan.md"), "[[index]]").unwrap();
+    std::fs::write(vault.join("a/todo.md"), "").unwrap();
+    std::fs::write(vault.join("b:c/todo.md"), "[[plan]]").unwrap();
+
+    let problems = check_vault(&vault).unwrap();
+    let found = |check: Check| -> Vec<String> {
+        problems
+            .iter()
+            .filter(|problem| problem.check == check)
+            .map(|problem| problem.subject.to_owned())
+            .collect()
+    };
+
+    assert_eq!(vec!["index.md"], found(Check::BrokenLink));
+    assert_eq!(vec!["b:c/todo.md"], found(Check::OrphanNote));
+    assert_eq!(vec!["a/todo.md"], found(Check::EmptyNote));
+    assert_eq!(vec!["todo"], found(Check::DuplicateName));
+    assert_eq!(vec!["b:c"], found(Check::InvalidName));
+
+    assert!(check_current_folder("vault", &vault, "a").is_none());
+    assert!(check_current_folder("vault", &vault, "gone").is_some());
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
@@ -1,6 +1,8 @@
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 
-#[derive(ValueEnum, Clone, Debug, PartialEq, Copy)]
+#[derive(ValueEnum, Clone, Debug, PartialEq, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Item {
     Vault,
     Vl,
@@ -56,6 +58,23 @@ impl VaultItem {
     }
 }
 
+#[derive(ValueEnum, Clone, Debug, PartialEq, Copy)]
+pub enum Format {
+    Text,
+    Json,
+    /// markdown, only for `export bundle`, whose text output is markdown
+    Md,
+}
+
+/// How links to other files are written.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum LinkStyle {
+    /// `![name](path)`
+    Markdown,
+    /// `![[name]]`
+    Wiki,
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum ConfigType {
     Editor,
@@ -63,6 +82,16 @@ pub enum ConfigType {
     VaultColor,
     FolderColor,
     NoteColor,
+    Inbox,
+    /// stored in the current vault rather than in the config file
+    #[clap(name = "git.autocommit")]
+    GitAutocommit,
+    /// stored in the current vault, e.g. `md,txt,org`
+    #[clap(name = "note.extensions")]
+    NoteExtensions,
+    /// stored in the current vault
+    #[clap(name = "note.extension")]
+    NoteExtension,
 }
 
 impl ConfigType {
@@ -73,6 +102,10 @@ impl ConfigType {
             ConfigType::VaultColor => "vault_color",
             ConfigType::FolderColor => "folder_color",
             ConfigType::NoteColor => "note_color",
+            ConfigType::Inbox => "inbox",
+            ConfigType::GitAutocommit => "git.autocommit",
+            ConfigType::NoteExtensions => "note.extensions",
+            ConfigType::NoteExtension => "note.extension",
         }
     }
 }
@@ -0,0 +1,733 @@
+/**
+ * Exports the notes of a vault as a static website that works offline, or
+ * as a single document.
+ */
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{copy, create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use crate::attachments::VaultFiles;
+use crate::ignore::JeffIgnore;
+use crate::markdown;
+use crate::prelude::*;
+
+/// Folders inside of the output folder. Notes and attachments keep their
+/// paths inside of `notes`, so they can't clash with the generated pages.
+const NOTES_FOLDER: &str = "notes";
+const TAGS_FOLDER: &str = "tags";
+const ASSETS_FOLDER: &str = "assets";
+
+const STYLE: &str = "\
+body { margin: 0; font: 16px/1.6 system-ui, sans-serif; color: #222; }
+header { display: flex; gap: 1em; align-items: center; padding: 0.5em 1em; border-bottom: 1px solid #ddd; position: relative; }
+header > a { font-weight: bold; text-decoration: none; color: inherit; }
+#search { flex: 1; max-width: 24em; padding: 0.3em 0.5em; }
+#results { position: absolute; top: 100%; left: 1em; margin: 0; padding: 0; list-style: none; background: #fff; box-shadow: 0 2px 8px #0003; }
+#results li a { display: block; padding: 0.3em 1em; }
+main { max-width: 48em; margin: 0 auto; padding: 1em; }
+pre { background: #f5f5f5; padding: 0.8em; overflow-x: auto; }
+img { max-width: 100%; }
+.tags a { margin-right: 0.5em; }
+.backlinks { border-top: 1px solid #ddd; margin-top: 2em; }
+";
+
+const SEARCH_SCRIPT: &str = r#"(function () {
+  var input = document.getElementById("search");
+  var results = document.getElementById("results");
+  var root = document.body.getAttribute("data-root");
+  var pages = window.JEFF_SEARCH_INDEX || [];
+
+  input.addEventListener("input", function () {
+    var terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    results.innerHTML = "";
+    if (!terms.length) {
+      return;
+    }
+
+    pages
+      .filter(function (page) {
+        var text = (page.title + " " + page.tags.join(" ") + " " + page.text).toLowerCase();
+        return terms.every(function (term) {
+          return text.indexOf(term) !== -1;
+        });
+      })
+      .slice(0, 20)
+      .forEach(function (page) {
+        var link = document.createElement("a");
+        link.href = root + page.path;
+        link.textContent = page.title;
+        var item = document.createElement("li");
+        item.appendChild(link);
+        results.appendChild(item);
+      });
+  });
+})();
+"#;
+
+/// The page of a note.
+struct Page {
+    /// absolute path to the note
+    note: PathBuf,
+    /// path to the page, relative to the output folder
+    path: PathBuf,
+    title: String,
+    tags: Vec<String>,
+}
+
+/// The notes of a vault, with what is needed to link them to each other.
+struct Site {
+    name: String,
+    files: VaultFiles,
+    /// the page of every note, by the note's path
+    pages: BTreeMap<PathBuf, Page>,
+    /// the notes that link to each note
+    backlinks: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+/**
+ * Renders every note of the vault at `vault_path` to HTML in `out`, along
+ * with a folder index, a page for each tag, and a search index. Attachments
+ * are copied next to the notes. Returns the number of exported notes.
+ */
+pub fn export_html(name: &str, vault_path: &Path, out: &Path) -> JeffResult<usize> {
+    let out = process_path(&std::path::absolute(out)?);
+    if out.starts_with(vault_path) && !JeffIgnore::load(vault_path)?.is_ignored(&out) {
+        return Err(Error::OutputInsideVault(out));
+    }
+
+    let site = Site::load(name, vault_path)?;
+    for page in site.pages.values() {
+        let contents = read_to_string(&page.note)?;
+        site.write(
+            &out,
+            &page.path,
+            &page.title,
+            &site.note_body(page, &contents),
+        )?;
+    }
+    for attachment in site.files.attachments() {
+        let path = out.join(site.output_path(attachment));
+        create_dir_all(path.parent().unwrap())?;
+        copy(attachment, path)?;
+    }
+
+    let mut tags: BTreeMap<String, Vec<&Page>> = BTreeMap::new();
+    for page in site.pages.values() {
+        for tag in &page.tags {
+            tags.entry(tag.to_lowercase()).or_default().push(page);
+        }
+    }
+    for (tag, pages) in &tags {
+        let path = tag_path(tag);
+        let mut body = format!("<h1>#{}</h1>\n<ul>\n", escape_html(tag));
+        for page in pages {
+            body.push_str(&site.page_item(&path, page));
+        }
+        body.push_str("</ul>\n");
+        site.write(&out, &path, &format!("#{}", tag), &body)?;
+    }
+
+    let index = PathBuf::from("index.html");
+    let mut body = format!("<h1>{}</h1>\n", escape_html(&site.name));
+    body.push_str(&site.folder_list(&index, Path::new("")));
+    if !tags.is_empty() {
+        body.push_str("<h2>Tags</h2>\n<p class=\"tags\">");
+        for tag in tags.keys() {
+            let href = href(&index, &tag_path(tag));
+            body.push_str(&format!(
+                "<a href=\"{}\">#{}</a>",
+                escape_html(&href),
+                escape_html(tag)
+            ));
+        }
+        body.push_str("</p>\n");
+    }
+    site.write(&out, &index, &site.name, &body)?;
+
+    let assets = out.join(ASSETS_FOLDER);
+    create_dir_all(&assets)?;
+    write(assets.join("style.css"), STYLE)?;
+    write(assets.join("search.js"), SEARCH_SCRIPT)?;
+    write(assets.join("search-index.js"), site.search_index()?)?;
+
+    Ok(site.pages.len())
+}
+
+impl Site {
+    fn load(name: &str, vault_path: &Path) -> JeffResult<Self> {
+        let files = VaultFiles::load(vault_path)?;
+
+        let mut site = Site {
+            name: name.to_owned(),
+            pages: BTreeMap::new(),
+            backlinks: BTreeMap::new(),
+            files,
+        };
+        for note in site.files.notes() {
+            let page = Page {
+                note: note.to_owned(),
+                path: site.output_path(note),
+                title: note
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                tags: markdown::tags(&read_to_string(note)?),
+            };
+            site.pages.insert(note.to_owned(), page);
+        }
+
+        for (note, link) in site.files.links()? {
+            match site.files.resolve(&note, &link) {
+                Some(target) if target != note && site.pages.contains_key(&target) => {
+                    site.backlinks.entry(target).or_default().insert(note);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(site)
+    }
+
+    /// Returns the path of the page or copy of a file of the vault, relative
+    /// to the output folder.
+    fn output_path(&self, path: &Path) -> PathBuf {
+        let output_path = Path::new(NOTES_FOLDER).join(self.files.relative(path));
+
+        // notes are sorted
+        if self
+            .files
+            .notes()
+            .binary_search(&path.to_path_buf())
+            .is_ok()
+        {
+            output_path.with_extension("html")
+        } else {
+            output_path
+        }
+    }
+
+    /// Wraps `body` in the layout shared by every page, and writes it to
+    /// `path`, relative to `out`.
+    fn write(&self, out: &Path, path: &Path, title: &str, body: &str) -> JeffResult<()> {
+        let root = href(path, Path::new(""));
+        let root = if root.is_empty() {
+            root
+        } else {
+            format!("{}/", root)
+        };
+
+        let page = format!(
+            "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<link rel=\"stylesheet\" href=\"{root}{assets}/style.css\">
+</head>
+<body data-root=\"{root}\">
+<header>
+<a href=\"{root}index.html\">{name}</a>
+<input type=\"search\" id=\"search\" placeholder=\"Search\" autocomplete=\"off\">
+<ul id=\"results\"></ul>
+</header>
+<main>
+{body}</main>
+<script src=\"{root}{assets}/search-index.js\"></script>
+<script src=\"{root}{assets}/search.js\"></script>
+</body>
+</html>
+",
+            title = escape_html(title),
+            name = escape_html(&self.name),
+            root = escape_html(&root),
+            assets = ASSETS_FOLDER,
+        );
+
+        let path = out.join(path);
+        create_dir_all(path.parent().unwrap())?;
+        write(path, page)?;
+
+        Ok(())
+    }
+
+    /// Returns the body of a note's page: the note, its tags and backlinks.
+    fn note_body(&self, page: &Page, contents: &str) -> String {
+        let mut body = format!("<h1>{}</h1>\n", escape_html(&page.title));
+        body.push_str(&self.render(page, contents).0);
+
+        if !page.tags.is_empty() {
+            body.push_str("<p class=\"tags\">");
+            for tag in &page.tags {
+                let href = href(&page.path, &tag_path(&tag.to_lowercase()));
+                body.push_str(&format!(
+                    "<a href=\"{}\">#{}</a>",
+                    escape_html(&href),
+                    escape_html(tag)
+                ));
+            }
+            body.push_str("</p>\n");
+        }
+
+        if let Some(backlinks) = self.backlinks.get(&page.note) {
+            body.push_str("<section class=\"backlinks\">\n<h2>Backlinks</h2>\n<ul>\n");
+            for note in backlinks {
+                body.push_str(&self.page_item(&page.path, &self.pages[note]));
+            }
+            body.push_str("</ul>\n</section>\n");
+        }
+
+        body
+    }
+
+    /**
+     * Renders the markdown of a note, without its frontmatter. Links to files
+     * of the vault point to their pages or copies, and embedded notes become
+     * links. HTML in the markdown is kept as it is. Notes that aren't
+     * markdown, like `.txt` and `.org` notes, are shown as they are written.
+     * Returns the HTML and the text of the note.
+     */
+    fn render(&self, page: &Page, contents: &str) -> (String, String) {
+        let is_markdown = page
+            .note
+            .extension()
+            .is_some_and(|extension| extension == "md" || extension == "markdown");
+        if !is_markdown {
+            let html = format!("<pre>{}</pre>\n", escape_html(contents));
+            return (html, contents.to_string());
+        }
+
+        let lines: Vec<&str> = contents.lines().collect();
+        let markdown = lines[markdown::frontmatter_end(&lines)..].join("\n");
+        let options = Options::ENABLE_WIKILINKS
+            | Options::ENABLE_TABLES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_FOOTNOTES;
+
+        let mut text = String::new();
+        // whether each open image is an embedded note, which is a link
+        let mut embedded_notes = vec![];
+        let events = Parser::new_ext(&markdown, options).map(|event| match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let wiki = matches!(link_type, LinkType::WikiLink { .. });
+                let dest_url = match self.link_href(page, &dest_url, wiki, false) {
+                    Some((href, _)) => CowStr::from(href),
+                    None => dest_url,
+                };
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                })
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let wiki = matches!(link_type, LinkType::WikiLink { .. });
+                let (dest_url, is_note) = match self.link_href(page, &dest_url, wiki, true) {
+                    Some((href, is_note)) => (CowStr::from(href), is_note),
+                    None => (dest_url, false),
+                };
+                embedded_notes.push(is_note);
+                if is_note {
+                    Event::Start(Tag::Link {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    })
+                } else {
+                    Event::Start(Tag::Image {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    })
+                }
+            }
+            Event::End(TagEnd::Image) if embedded_notes.pop() == Some(true) => {
+                Event::End(TagEnd::Link)
+            }
+            Event::Text(ref content) | Event::Code(ref content) => {
+                text.push_str(content);
+                text.push(' ');
+                event
+            }
+            event => event,
+        });
+
+        let mut html = String::new();
+        html::push_html(&mut html, events);
+
+        (html, text)
+    }
+
+    /// Returns the href of a link in `page`, and whether it points to a note,
+    /// or `None` for links that are left alone, like URLs. Wiki links to
+    /// missing files point nowhere.
+    fn link_href(
+        &self,
+        page: &Page,
+        dest: &str,
+        wiki: bool,
+        embed: bool,
+    ) -> Option<(String, bool)> {
+        let (target, fragment) = match dest.split_once('#') {
+            Some((target, fragment)) => (target, Some(fragment)),
+            None => (dest, None),
+        };
+        if target.trim().is_empty() || (!wiki && markdown::is_url(target)) {
+            return None;
+        }
+
+        let link = Link {
+            target: if wiki {
+                target.trim().to_string()
+            } else {
+                percent_decode(target)
+            },
+            embed,
+            wiki,
+        };
+        let Some(path) = self.files.resolve(&page.note, &link) else {
+            return wiki.then(|| ("#".to_string(), false));
+        };
+
+        let mut href = href(&page.path, &self.output_path(&path));
+        // wiki links name headings by their text, which pages have no ids for
+        if let (Some(fragment), false) = (fragment, wiki) {
+            href.push('#');
+            href.push_str(fragment);
+        }
+
+        Some((href, self.pages.contains_key(&path)))
+    }
+
+    /// Returns a list item linking to `page` from the page at `from`.
+    fn page_item(&self, from: &Path, page: &Page) -> String {
+        format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            escape_html(&href(from, &page.path)),
+            escape_html(&page.title)
+        )
+    }
+
+    /// Returns the nested list of the folders and notes inside of `folder`,
+    /// relative to the vault, for the page at `from`.
+    fn folder_list(&self, from: &Path, folder: &Path) -> String {
+        let mut folders = BTreeSet::new();
+        let mut pages = vec![];
+        for page in self.pages.values() {
+            let relative = self.files.relative(&page.note);
+            let Ok(inside) = relative.strip_prefix(folder) else {
+                continue;
+            };
+            let mut components = inside.iter();
+            match (components.next(), components.next()) {
+                (Some(name), Some(_)) => {
+                    folders.insert(folder.join(name));
+                }
+                (Some(_), None) => pages.push(page),
+                _ => {}
+            }
+        }
+
+        let mut list = "<ul>\n".to_string();
+        for folder in folders {
+            list.push_str(&format!(
+                "<li><strong>{}</strong>\n{}</li>\n",
+                escape_html(&folder.file_name().unwrap_or_default().to_string_lossy()),
+                self.folder_list(from, &folder)
+            ));
+        }
+        for page in pages {
+            list.push_str(&self.page_item(from, page));
+        }
+        list.push_str("</ul>\n");
+
+        list
+    }
+
+    /// Returns the script with the search index: the title, tags, path and
+    /// text of every page.
+    fn search_index(&self) -> JeffResult<String> {
+        let mut entries = vec![];
+        for page in self.pages.values() {
+            let (_, text) = self.render(page, &read_to_string(&page.note)?);
+            entries.push(json!({
+                "title": page.title,
+                "path": path_to_string(page.path.to_owned()).replace('\\', "/"),
+                "tags": page.tags,
+                "text": text,
+            }));
+        }
+
+        Ok(format!(
+            "window.JEFF_SEARCH_INDEX = {};\n",
+            serde_json::to_string(&entries).unwrap()
+        ))
+    }
+}
+
+/// A note inside of a [Bundle].
+#[derive(Debug, Clone)]
+pub struct BundledNote {
+    /// path to the note, relative to the vault
+    pub path: PathBuf,
+    pub frontmatter: Vec<(String, FrontmatterValue)>,
+    /// the note, without its frontmatter
+    pub body: String,
+}
+
+impl BundledNote {
+    /// The heading of the note in a markdown bundle: its path, without the
+    /// extension.
+    fn heading(&self) -> String {
+        path_to_string(self.path.with_extension("")).replace('\\', "/")
+    }
+}
+
+/// Notes put together into one document, for people who don't use jeff.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub notes: Vec<BundledNote>,
+}
+
+impl Bundle {
+    /// Returns the notes one after another, each under a heading with its
+    /// path.
+    pub fn to_markdown(&self) -> String {
+        self.notes
+            .iter()
+            .map(|note| format!("# {}\n\n{}", note.heading(), note.body.trim()))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    /// Returns the path, frontmatter and body of every note.
+    pub fn to_json(&self) -> Value {
+        let notes = self.notes.iter().map(|note| {
+            let frontmatter: Map<String, Value> = note
+                .frontmatter
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        FrontmatterValue::Text(text) => json!(text),
+                        FrontmatterValue::List(items) => json!(items),
+                    };
+                    (key.to_owned(), value)
+                })
+                .collect();
+
+            json!({
+                "path": path_to_string(note.path.to_owned()).replace('\\', "/"),
+                "frontmatter": frontmatter,
+                "body": note.body,
+            })
+        });
+
+        Value::Array(notes.collect())
+    }
+}
+
+/**
+ * Bundles `notes` of the vault at `vault_path`, in order. With
+ * `inline_links`, wiki links to bundled notes become links to their
+ * headings, and the other wiki links become their text. Embeds are kept.
+ */
+pub fn bundle(vault_path: &Path, notes: &[PathBuf], inline_links: bool) -> JeffResult<Bundle> {
+    let files = VaultFiles::load(vault_path)?;
+    let mut bundle = Bundle { notes: vec![] };
+
+    for note in notes {
+        let contents = read_to_string(note)?;
+        let lines: Vec<&str> = contents.lines().collect();
+        bundle.notes.push(BundledNote {
+            path: files.relative(note),
+            frontmatter: markdown::frontmatter(&contents),
+            body: lines[markdown::frontmatter_end(&lines)..].join("\n"),
+        });
+    }
+
+    if inline_links {
+        let anchors: BTreeMap<PathBuf, String> = bundle
+            .notes
+            .iter()
+            .map(|note| (vault_path.join(&note.path), heading_anchor(&note.heading())))
+            .collect();
+
+        for (note, bundled) in notes.iter().zip(bundle.notes.iter_mut()) {
+            bundled.body = replace_wiki_links(&bundled.body, |target, alias, embed| {
+                if embed {
+                    return None;
+                }
+
+                let link = Link {
+                    target: target.split('#').next().unwrap_or_default().to_string(),
+                    embed,
+                    wiki: true,
+                };
+                let text = alias.unwrap_or(target);
+                Some(
+                    match files
+                        .resolve(note, &link)
+                        .and_then(|path| anchors.get(&path))
+                    {
+                        Some(anchor) => format!("[{}](#{})", text, anchor),
+                        None => text.to_string(),
+                    },
+                )
+            });
+        }
+    }
+
+    Ok(bundle)
+}
+
+/// Returns the path of the page of a tag, relative to the output folder.
+fn tag_path(tag: &str) -> PathBuf {
+    Path::new(TAGS_FOLDER).join(format!("{}.html", tag))
+}
+
+/// Returns the href from the page at `from` to `path`, both relative to the
+/// output folder.
+fn href(from: &Path, path: &Path) -> String {
+    let relative = relative_path(from.parent().unwrap_or(Path::new("")), path);
+    let relative = path_to_string(relative).replace('\\', "/");
+
+    percent_encode(&relative)
+        .replace('#', "%23")
+        .replace('?', "%3F")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[test]
+fn export_html_tests() {
+    let folder = std::env::temp_dir().join(format!("jeff_export_{}", std::process::id()));
+    let (vault, out) = (folder.join("vault"), folder.join("site"));
+    std::fs::create_dir_all(vault.join(".jeff")).unwrap();
+    std::fs::create_dir_all(vault.join("projects/attachments")).unwrap();
+    std::fs::write(
+        vault.join("home.md"),
+        "# Home\nSee [[roadmap|the plan]] and [[gone]]. #work",
+    )
+    .unwrap();
+    std::fs::write(
+        vault.join("projects/roadmap.md"),
+        "---\ntags: [planning]\n---\n![[board.png]] [home](../home.md) [site](https://example.com)",
+    )
+    .unwrap();
+    std::fs::write(vault.join("projects/attachments/board.png"), "png").unwrap();
+    std::fs::write(
+        vault.join(".jeff/data"),
+        "schema_version = 2\n[aliases]\n[notes]\nextensions = ['md', 'txt']\n",
+    )
+    .unwrap();
+    std::fs::write(vault.join("todo.txt"), "# not a heading <b>\n").unwrap();
+    // tags that are paths can't write pages outside of the tags folder
+    std::fs::write(
+        vault.join("pwned.md"),
+        "---\ntags: [../../pwned, ../index, ../notes/home]\n---\n",
+    )
+    .unwrap();
+
+    assert!(export_html("notes", &vault, &vault.join("site")).is_err());
+    assert_eq!(4, export_html("notes", &vault, &out).unwrap());
+    assert!(!folder.join("pwned.html").exists());
+    assert_eq!(
+        vec!["planning.html", "work.html"],
+        std::fs::read_dir(out.join("tags"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect::<Vec<String>>()
+    );
+
+    let todo = read_to_string(out.join("notes/todo.html")).unwrap();
+    assert!(todo.contains("<pre># not a heading &lt;b&gt;\n</pre>"));
+
+    let home = read_to_string(out.join("notes/home.html")).unwrap();
+    assert!(home.contains("<a href=\"projects/roadmap.html\">the plan</a>"));
+    assert!(home.contains("<a href=\"#\">gone</a>"));
+    assert!(home.contains("href=\"../tags/work.html\""));
+    assert!(home.contains("<link rel=\"stylesheet\" href=\"../assets/style.css\">"));
+
+    let roadmap = read_to_string(out.join("notes/projects/roadmap.html")).unwrap();
+    assert!(roadmap.contains("<img src=\"attachments/board.png\""));
+    assert!(roadmap.contains("<a href=\"../home.html\">home</a>"));
+    assert!(roadmap.contains("<a href=\"https://example.com\">site</a>"));
+    assert!(!roadmap.contains("tags: [planning]"));
+    // backlinks
+    assert!(roadmap.contains("<li><a href=\"../home.html\">home</a></li>"));
+
+    assert!(out.join("notes/projects/attachments/board.png").is_file());
+    let planning = read_to_string(out.join("tags/planning.html")).unwrap();
+    assert!(planning.contains("<li><a href=\"../notes/projects/roadmap.html\">roadmap</a></li>"));
+    let index = read_to_string(out.join("index.html")).unwrap();
+    assert!(index.contains("<li><strong>projects</strong>"));
+    let search_index = read_to_string(out.join("assets/search-index.js")).unwrap();
+    assert!(search_index.contains("\"path\":\"notes/home.html\""));
+
+    std::fs::remove_dir_all(&folder).unwrap();
+}
+
+#[test]
+fn bundle_tests() {
+    let vault = std::env::temp_dir().join(format!("jeff_bundle_{}", std::process::id()));
+    std::fs::create_dir_all(vault.join(".jeff")).unwrap();
+    std::fs::create_dir_all(vault.join("projects")).unwrap();
+    let (home, roadmap) = (vault.join("home.md"), vault.join("projects/roadmap.md"));
+    std::fs::write(
+        &home,
+        "See [[roadmap|the plan]], [[gone]] and ![[board.png]].",
+    )
+    .unwrap();
+    std::fs::write(
+        &roadmap,
+        "---\ntitle: Roadmap\ntags: [planning]\n---\nShip it.\n",
+    )
+    .unwrap();
+    let notes = vec![home, roadmap];
+
+    let bundled = bundle(&vault, &notes, false).unwrap();
+    assert_eq!(
+        "# home\n\nSee [[roadmap|the plan]], [[gone]] and ![[board.png]].\n\n# projects/roadmap\n\nShip it.",
+        bundled.to_markdown()
+    );
+    assert_eq!(
+        json!([
+            { "path": "home.md", "frontmatter": {}, "body": "See [[roadmap|the plan]], [[gone]] and ![[board.png]]." },
+            { "path": "projects/roadmap.md", "frontmatter": { "title": "Roadmap", "tags": ["planning"] }, "body": "Ship it." },
+        ]),
+        bundled.to_json()
+    );
+
+    let inlined = bundle(&vault, &notes, true).unwrap();
+    assert_eq!(
+        "See [the plan](#projectsroadmap), gone and ![[board.png]].",
+        inlined.notes[0].body
+    );
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
@@ -1,64 +1,368 @@
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     fmt::Debug,
-    fs::{create_dir_all, read_to_string, File},
+    fs::{copy, create_dir_all, read_to_string, remove_file, rename, File},
     io::{ErrorKind, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
+use toml::{value::Table, Value};
+
+use crate::output::error::{Error, JeffResult};
+
+/// Upgrades the contents of a file from one schema version to the next.
+pub type Migration = fn(&mut Table);
+
 pub trait FileIO: Debug + Default + Serialize + DeserializeOwned {
+    /// Version of the file format, stored in the file as `schema_version`.
+    /// Bump it, add a step to [FileIO::migrations] and add a fixture for the
+    /// new version under `src/tests/fixtures` whenever the format changes.
+    const SCHEMA_VERSION: u32;
+
     fn path(&self) -> PathBuf;
 
-    fn load() -> Self {
+    /// `migrations()[n]` upgrades a file from version `n` to `n + 1`.
+    /// Files written before `schema_version` was added are version `0`.
+    fn migrations() -> Vec<Migration>;
+
+    fn load() -> JeffResult<Self> {
         let path = <Self as FileIO>::path(&Self::default());
 
         <Self as FileIO>::load_path(path)
     }
 
-    fn load_path(path: PathBuf) -> Self {
+    /**
+     * Loads the file at the given path, creating it if it doesn't exist.
+     * Files from older versions are upgraded in place, after backing them up
+     * to `<file>.v<version>.bak`. Fails with [Error::CorruptedFile] if the
+     * file can't be parsed.
+     */
+    fn load_path(path: PathBuf) -> JeffResult<Self> {
         match read_to_string(&path) {
             Ok(file_string) => {
-                if let Ok(file_data) = toml::from_str::<Self>(&file_string) {
-                    file_data
-                } else {
-                    toml::from_str::<Self>(&file_string).unwrap();
-                    panic!("couldn't parse data")
+                let (data, version) = <Self as FileIO>::parse(&path, &file_string)?;
+
+                if version < Self::SCHEMA_VERSION {
+                    let _lock = FileLock::acquire(&path)?;
+                    let backup = with_suffix(&path, &format!(".v{}.bak", version));
+                    copy(&path, &backup).map_err(|err| file_error(&backup, err))?;
+                    <Self as FileIO>::write_atomic(&path, &data)?;
                 }
+
+                Ok(data)
             }
             Err(ref err) if err.kind() == ErrorKind::NotFound => {
                 <Self as FileIO>::create_file(path)
             }
-            Err(_) => panic!("couldn't load file"),
+            Err(err) => Err(file_error(&path, err)),
         }
     }
 
-    fn store(&self) {
+    /**
+     * Applies `change` to the latest contents of the file and stores the
+     * result. The file stays locked in between, so concurrent `jf` processes
+     * don't overwrite each other's updates. A file that can't be parsed is
+     * left untouched.
+     */
+    fn update<F: FnOnce(&mut Self)>(&mut self, change: F) -> JeffResult<()> {
         let path = <Self as FileIO>::path(self);
+        let _lock = FileLock::acquire(&path)?;
 
-        let mut file = File::options()
-            .write(true)
-            .truncate(true)
-            .open(path)
-            .unwrap();
+        match read_to_string(&path) {
+            Ok(file_string) => *self = <Self as FileIO>::parse(&path, &file_string)?.0,
+            Err(ref err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(file_error(&path, err)),
+        }
+        change(self);
 
-        <Self as FileIO>::write_file(&mut file, self)
+        <Self as FileIO>::write_atomic(&path, self)
     }
 
-    fn create_file(path: PathBuf) -> Self {
-        create_dir_all(&path.parent().unwrap()).unwrap();
+    fn create_file(path: PathBuf) -> JeffResult<Self> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).map_err(|err| file_error(&path, err))?;
+        }
 
-        let mut file = File::options().create(true).write(true).open(path).unwrap();
+        let _lock = FileLock::acquire(&path)?;
+        // another process may have created the file while we waited for the lock
+        if let Ok(file_string) = read_to_string(&path) {
+            return <Self as FileIO>::parse(&path, &file_string).map(|(data, _)| data);
+        }
 
         let data = Self::default();
+        <Self as FileIO>::write_atomic(&path, &data)?;
+
+        Ok(data)
+    }
+
+    /**
+     * Parses the contents of a file, running the migrations needed to bring
+     * it up to [FileIO::SCHEMA_VERSION]. Returns the data and the version the
+     * file was written with.
+     */
+    fn parse(path: &Path, file_string: &str) -> JeffResult<(Self, u32)> {
+        let corrupted =
+            |error: toml::de::Error| Error::CorruptedFile(path.into(), error.to_string());
+
+        let mut table = toml::from_str::<Table>(file_string).map_err(corrupted)?;
+        let version = match table.get("schema_version") {
+            None => 0,
+            Some(Value::Integer(version)) if *version >= 0 => *version as u32,
+            Some(value) => {
+                return Err(Error::CorruptedFile(
+                    path.into(),
+                    format!("invalid schema_version {}", value),
+                ))
+            }
+        };
+
+        if version > Self::SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchema(
+                path.into(),
+                version,
+                Self::SCHEMA_VERSION,
+            ));
+        }
+
+        if version == Self::SCHEMA_VERSION {
+            // parse the text directly, so that errors report their position
+            let data = toml::from_str::<Self>(file_string).map_err(corrupted)?;
+            return Ok((data, version));
+        }
+
+        for migration in &Self::migrations()[version as usize..] {
+            migration(&mut table);
+        }
+        table.insert(
+            "schema_version".to_string(),
+            Value::Integer(Self::SCHEMA_VERSION as i64),
+        );
+        let data = Value::Table(table).try_into::<Self>().map_err(corrupted)?;
+
+        Ok((data, version))
+    }
+
+    /**
+     * Writes to a temporary file next to the original and renames it over
+     * the original, so the file is never left half-written.
+     */
+    fn write_atomic(path: &Path, data: &Self) -> JeffResult<()> {
+        let temp_path = with_suffix(path, &format!(".{}.tmp", std::process::id()));
+
+        let result = File::create(&temp_path)
+            .and_then(|mut file| {
+                <Self as FileIO>::write_file(&mut file, data)?;
+                file.sync_all()
+            })
+            .and_then(|_| rename(&temp_path, path));
+
+        if let Err(err) = result {
+            let _ = remove_file(&temp_path);
+            return Err(file_error(path, err));
+        }
+
+        // Sync the directory so that the rename itself survives a crash.
+        if let Some(parent) = path.parent() {
+            let _ = File::open(parent).and_then(|dir| dir.sync_all());
+        }
+
+        Ok(())
+    }
+
+    fn write_file(file: &mut File, data: &Self) -> std::io::Result<()> {
+        let data_string = toml::to_string_pretty(&data)
+            .map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))?;
+
+        file.write_all(data_string.as_bytes())
+    }
+}
+
+/**
+ * Advisory lock on `<file>.lock`, held until it is dropped. Every process
+ * that writes the file takes the lock first, so writes are serialised.
+ */
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    pub fn acquire(path: &Path) -> JeffResult<Self> {
+        let lock_path = with_suffix(path, ".lock");
+
+        let file = File::options()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&lock_path)
+            .and_then(|file| file.lock().map(|_| file))
+            .map_err(|err| file_error(&lock_path, err))?;
 
-        <Self as FileIO>::write_file(&mut file, &data);
+        Ok(FileLock { _file: file })
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn file_error(path: &Path, error: std::io::Error) -> Error {
+    Error::FileError(path.display().to_string(), error)
+}
+
+/**
+ * Moves a corrupted file to `<file>.bak` so that it is recreated with
+ * default values the next time it is loaded. Returns the path to the backup.
+ */
+pub fn back_up_file(path: &Path) -> JeffResult<PathBuf> {
+    let backup = with_suffix(path, ".bak");
+
+    rename(path, &backup).map_err(|err| file_error(path, err))?;
+
+    Ok(backup)
+}
+
+#[test]
+fn corrupted_file_tests() {
+    use crate::state::data::Data;
+
+    let dir = std::env::temp_dir().join(format!("jeff_fileio_{}", std::process::id()));
+    let path = dir.join("vaults");
+
+    // missing files are created with default values
+    assert!(Data::load_path(path.clone()).is_ok());
+    assert!(path.exists());
+
+    std::fs::write(&path, "current = \"vault\"\nvaults = [\n").unwrap();
+    match Data::load_path(path.clone()) {
+        Err(Error::CorruptedFile(error_path, error)) => {
+            assert_eq!(path, error_path);
+            assert!(error.contains("line"), "{}", error);
+        }
+        result => panic!("expected a corrupted file error, got {:?}", result),
+    }
+
+    let backup = back_up_file(&path).unwrap();
+    assert!(backup.exists());
+    assert!(!path.exists());
+    assert!(Data::load_path(path.clone()).is_ok());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn concurrent_update_tests() {
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    struct Counter {
+        count: u32,
+    }
+
+    impl FileIO for Counter {
+        const SCHEMA_VERSION: u32 = 0;
+
+        fn path(&self) -> PathBuf {
+            std::env::temp_dir().join(format!("jeff_counter_{}", std::process::id()))
+        }
 
-        data
+        fn migrations() -> Vec<Migration> {
+            vec![]
+        }
     }
 
-    fn write_file(file: &mut File, data: &Self) {
-        let data_string = toml::to_string_pretty(&data).unwrap();
+    let path = Counter::default().path();
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            std::thread::spawn(|| {
+                let mut counter = Counter::load().unwrap();
+                for _ in 0..10 {
+                    counter.update(|counter| counter.count += 1).unwrap();
+                }
+            })
+        })
+        .collect();
+    threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    assert_eq!(80, Counter::load_path(path.clone()).unwrap().count);
+
+    // a corrupted file isn't overwritten
+    std::fs::write(&path, "count = ").unwrap();
+    let mut counter = Counter::default();
+    assert!(matches!(
+        counter.update(|counter| counter.count += 1),
+        Err(Error::CorruptedFile(..))
+    ));
+    assert_eq!("count = ", std::fs::read_to_string(&path).unwrap());
+
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(with_suffix(&path, ".lock")).unwrap();
+}
+
+#[test]
+fn schema_migration_tests() {
+    use crate::{items::VaultStore, state::data::Data, state::Config};
 
-        file.write_all(data_string.as_bytes()).unwrap();
+    fn load_fixture<T: FileIO>(name: &str, version: u32, fixture: &str) {
+        let dir = std::env::temp_dir().join(format!("jeff_schema_{}", std::process::id()));
+        let path = dir.join(format!("{}_v{}", name, version));
+        create_dir_all(&dir).unwrap();
+        std::fs::write(&path, fixture).unwrap();
+
+        assert!(T::load_path(path.clone()).is_ok(), "{} v{}", name, version);
+
+        let migrated = read_to_string(&path).unwrap();
+        assert!(migrated.contains(&format!("schema_version = {}", T::SCHEMA_VERSION)));
+        let backup = with_suffix(&path, &format!(".v{}.bak", version));
+        assert_eq!(version < T::SCHEMA_VERSION, backup.exists());
+        if backup.exists() {
+            assert_eq!(fixture, read_to_string(&backup).unwrap());
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    macro_rules! fixtures {
+        ($type:ty, $name:literal, [$($version:literal),*]) => {
+            $(load_fixture::<$type>(
+                $name,
+                $version,
+                include_str!(concat!("tests/fixtures/", $name, "/v", $version, ".toml")),
+            );)*
+        };
     }
+
+    fixtures!(Config, "config", [0, 1]);
+    fixtures!(Data, "data", [0, 1, 2]);
+    fixtures!(VaultStore, "vault_store", [0, 1, 2]);
+
+    // vaults had no git or notes settings before version 2
+    let (store, _) = VaultStore::parse(
+        Path::new("data"),
+        include_str!("tests/fixtures/vault_store/v1.toml"),
+    )
+    .unwrap();
+    assert!(!store.git_autocommit());
+    assert_eq!(vec!["md".to_string()], store.note_extensions());
+    assert_eq!("md", store.default_note_extension());
+
+    // vaults stored the folder containing them before version 2
+    let (data, _) = Data::parse(
+        Path::new("data"),
+        include_str!("tests/fixtures/data/v1.toml"),
+    )
+    .unwrap();
+    assert_eq!(
+        Some(&PathBuf::from("/home/jeff/notes")),
+        data.get_vault_path("notes")
+    );
+
+    let newer = format!("schema_version = {}\n", Data::SCHEMA_VERSION + 1);
+    assert!(matches!(
+        Data::parse(Path::new("data"), &newer),
+        Err(Error::UnsupportedSchema(_, _, _))
+    ));
 }
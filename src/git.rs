@@ -0,0 +1,209 @@
+use chrono::{DateTime, Local};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::prelude::*;
+
+/// Runs `git` inside of `repo`, returning its output if it succeeded.
+fn git(repo: &Path, args: &[&str], paths: &[PathBuf]) -> JeffResult<Output> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .args(paths)
+        .output()
+        .map_err(|err| Error::GitFailed(format!("couldn't run git: {}", err)))?;
+
+    if output.status.success() {
+        Ok(output)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(Error::GitFailed(stderr.trim().to_string()))
+    }
+}
+
+/// Whether the file at `path` is known to git (it may have been removed).
+pub fn is_tracked(repo: &Path, path: &Path) -> JeffResult<bool> {
+    let output = git(repo, &["ls-files", "--"], &[path.to_path_buf()])?;
+    Ok(!output.stdout.is_empty())
+}
+
+/// Returns the root of the work tree that `path` is inside of.
+pub fn toplevel(path: &Path) -> JeffResult<PathBuf> {
+    let output = git(path, &["rev-parse", "--show-toplevel"], &[])?;
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+/// A commit that changed a file.
+pub struct FileCommit {
+    /// abbreviated hash
+    pub hash: String,
+    pub time: DateTime<Local>,
+    pub subject: String,
+    /// path to the file in this commit, relative to the top of the repository
+    pub path: String,
+}
+
+/// Returns the commits that changed the file at `path`, newest first.
+/// Renames are followed, but copies (which git also detects between empty
+/// files) are not.
+pub fn file_log(repo: &Path, path: &Path) -> JeffResult<Vec<FileCommit>> {
+    let output = git(
+        repo,
+        &[
+            "log",
+            "--follow",
+            "--format=%x1e%h%x09%aI%x09%s",
+            "--name-status",
+            "--",
+        ],
+        &[path.to_path_buf()],
+    )?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut commits = vec![];
+    for entry in stdout
+        .split('\x1e')
+        .filter(|entry| !entry.trim().is_empty())
+    {
+        let mut lines = entry.lines().filter(|line| !line.is_empty());
+        let header: Vec<&str> = lines.next().unwrap_or_default().splitn(3, '\t').collect();
+        // e.g. `M\tnote.md` or `R100\told.md\tnote.md`
+        let status: Vec<&str> = lines.next().unwrap_or_default().split('\t').collect();
+        let (Some(path), [hash, time, subject]) = (status.last(), header.as_slice()) else {
+            continue;
+        };
+        let Ok(time) = DateTime::parse_from_rfc3339(time) else {
+            continue;
+        };
+
+        commits.push(FileCommit {
+            hash: hash.to_string(),
+            time: time.with_timezone(&Local),
+            subject: subject.to_string(),
+            path: path.to_string(),
+        });
+
+        if status[0].starts_with('C') {
+            break;
+        }
+    }
+
+    Ok(commits)
+}
+
+/// Returns the contents of a file in the given commit. `path` is relative
+/// to the top of the repository.
+pub fn show(repo: &Path, hash: &str, path: &str) -> JeffResult<String> {
+    let output = git(repo, &["show", &format!("{}:{}", hash, path)], &[])?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/**
+ * Stages the given paths and commits them with `message`. Paths outside of
+ * `repo`, `repo` itself (for changes to the vault's settings) and jeff's own
+ * data inside of `.jeff` are skipped. Nothing is committed if none of the
+ * paths changed.
+ */
+pub fn commit(repo: &Path, paths: &[PathBuf], message: &str) -> JeffResult<()> {
+    git(repo, &["rev-parse", "--is-inside-work-tree"], &[]).map_err(|_| {
+        Error::GitFailed(format!(
+            "{} is not inside of a git repository",
+            repo.display()
+        ))
+    })?;
+
+    let mut staged = vec![];
+    for path in paths {
+        let is_jeff_data = path
+            .components()
+            .any(|component| component == Component::Normal(".jeff".as_ref()));
+        let is_vault = path == repo;
+        if !path.starts_with(repo) || is_vault || is_jeff_data || staged.contains(path) {
+            continue;
+        }
+        if path.exists() || is_tracked(repo, path)? {
+            staged.push(path.to_owned());
+        }
+    }
+
+    if staged.is_empty() {
+        return Ok(());
+    }
+
+    git(repo, &["add", "-A", "--"], &staged)?;
+
+    // `diff --quiet` fails when there are staged changes
+    if git(repo, &["diff", "--cached", "--quiet", "--"], &staged).is_ok() {
+        return Ok(());
+    }
+
+    git(repo, &["commit", "-q", "-m", message, "--"], &staged)?;
+
+    Ok(())
+}
+
+#[test]
+fn commit_tests() {
+    let repo = std::env::temp_dir().join(format!("jeff_git_{}", std::process::id()));
+    let note = repo.join("note.md");
+    std::fs::create_dir_all(repo.join(".jeff")).unwrap();
+    std::fs::write(&note, "contents").unwrap();
+    std::fs::write(repo.join("other.md"), "not staged").unwrap();
+    std::fs::write(repo.join(".jeff/data"), "current_folder = 'a'").unwrap();
+
+    // not a repository yet
+    assert!(commit(&repo, std::slice::from_ref(&note), "jf: create note note").is_err());
+
+    for args in [
+        vec!["init", "-q"],
+        vec!["config", "user.name", "jeff"],
+        vec!["config", "user.email", "jeff@example.com"],
+    ] {
+        git(&repo, &args, &[]).unwrap();
+    }
+
+    let log = |repo: &Path| {
+        let output = git(repo, &["log", "--format=%s", "--name-status"], &[]).unwrap();
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let paths = [note.clone(), repo.join(".jeff/data"), repo.clone()];
+    commit(&repo, &paths, "jf: create note note").unwrap();
+    assert!(log(&repo).starts_with("jf: create note note\n\nA\tnote.md"));
+    assert!(!log(&repo).contains("other.md"));
+
+    // unchanged files aren't committed again
+    commit(&repo, std::slice::from_ref(&note), "jf: edit note note").unwrap();
+    assert!(!log(&repo).contains("edit note"));
+
+    let renamed = repo.join("renamed.md");
+    std::fs::rename(&note, &renamed).unwrap();
+    let paths = [note.clone(), renamed.clone()];
+    commit(&repo, &paths, "jf: rename note note -> renamed").unwrap();
+    assert!(log(&repo).starts_with("jf: rename note note -> renamed\n\nR100\tnote.md\trenamed.md"));
+
+    std::fs::write(&renamed, "new contents").unwrap();
+    commit(
+        &repo,
+        std::slice::from_ref(&renamed),
+        "jf: edit note renamed",
+    )
+    .unwrap();
+    let commits = file_log(&repo, &renamed).unwrap();
+    assert_eq!(3, commits.len());
+    assert_eq!("jf: create note note", commits[2].subject);
+    assert_eq!("note.md", commits[2].path);
+    assert_eq!(
+        "contents",
+        show(&repo, &commits[2].hash, &commits[2].path).unwrap()
+    );
+    assert_eq!(
+        "new contents",
+        show(&repo, &commits[0].hash, &commits[0].path).unwrap()
+    );
+
+    std::fs::remove_dir_all(&repo).unwrap();
+}
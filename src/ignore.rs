@@ -0,0 +1,278 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+/// Patterns that apply to every vault, before the ones in `.jeffignore`.
+const DEFAULT_PATTERNS: &str = "\
+# hidden folders, like .git, .obsidian and .trash
+.*/
+";
+
+/// A line of a `.jeffignore` file.
+#[derive(Debug)]
+struct Pattern {
+    glob: Vec<char>,
+    /// `!pattern` includes paths that an earlier pattern ignored
+    negated: bool,
+    /// `pattern/` only matches folders
+    folders_only: bool,
+    /// patterns with a slash match paths relative to the vault, the others
+    /// match names at any depth
+    anchored: bool,
+}
+
+impl Pattern {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let (folders_only, line) = match line.strip_suffix('/') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(Pattern {
+            glob: line.chars().collect(),
+            negated,
+            folders_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, relative_path: &str, is_folder: bool) -> bool {
+        if self.folders_only && !is_folder {
+            return false;
+        }
+
+        let text = if self.anchored {
+            relative_path
+        } else {
+            relative_path.rsplit('/').next().unwrap_or(relative_path)
+        };
+
+        glob_match(&self.glob, &text.chars().collect::<Vec<char>>())
+    }
+}
+
+/**
+ * Gitignore-style patterns for the paths that aren't part of a vault. They
+ * are read from `<vault>/.jeffignore`, after built-in defaults that ignore
+ * hidden folders. `.jeff` is always ignored.
+ */
+#[derive(Debug)]
+pub struct JeffIgnore {
+    root: PathBuf,
+    patterns: Vec<Pattern>,
+    /// extensions of the files that are notes in this vault
+    note_extensions: Vec<String>,
+}
+
+impl JeffIgnore {
+    /// Reads the patterns of the vault at `root`.
+    pub fn load(root: &Path) -> JeffResult<Self> {
+        let mut patterns: Vec<Pattern> = DEFAULT_PATTERNS
+            .lines()
+            .filter_map(Pattern::parse)
+            .collect();
+
+        let ignore_file = root.join(".jeffignore");
+        if ignore_file.is_file() {
+            patterns.extend(
+                read_to_string(ignore_file)?
+                    .lines()
+                    .filter_map(Pattern::parse),
+            );
+        }
+
+        let store_path = root.join(".jeff/data");
+        let note_extensions = if store_path.is_file() {
+            VaultStore::load_path(store_path)?.note_extensions()
+        } else {
+            vec![DEFAULT_NOTE_EXTENSION.to_string()]
+        };
+
+        Ok(JeffIgnore {
+            root: root.to_path_buf(),
+            patterns,
+            note_extensions,
+        })
+    }
+
+    /// Reads the patterns of the vault that contains `path`. Outside of
+    /// vaults, only the defaults apply.
+    pub fn for_path(path: &Path) -> JeffResult<Self> {
+        match Vault::root_of(path) {
+            Some(root) => JeffIgnore::load(&root),
+            None => JeffIgnore::load(path),
+        }
+    }
+
+    /// Whether `path` is a note with one of the vault's note extensions.
+    /// Files with other extensions are attachments.
+    pub fn is_note(&self, path: &Path) -> bool {
+        Note::is_valid_path(&path.to_path_buf())
+            && self.note_extensions.iter().any(|extension| {
+                path.extension()
+                    .is_some_and(|ext| ext == extension.as_str())
+            })
+    }
+
+    /// Whether `path`, or a folder that contains it, is ignored.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative_path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let mut current = PathBuf::new();
+        for component in relative_path.iter() {
+            current.push(component);
+            let is_folder = current != relative_path || path.is_dir();
+            if component == ".jeff" || self.ignores(&current, is_folder) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Whether the last pattern matching `relative_path` ignores it.
+    fn ignores(&self, relative_path: &Path, is_folder: bool) -> bool {
+        let relative_path = path_to_string(relative_path.to_path_buf()).replace('\\', "/");
+
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(&relative_path, is_folder))
+            .is_some_and(|pattern| !pattern.negated)
+    }
+}
+
+/// Matches `text` against a glob with `*`, `**`, `?`, `[...]` and `\`
+/// escapes. Only `**` matches across slashes.
+fn glob_match(glob: &[char], text: &[char]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` also matches no folders at all
+            if let ['/', after @ ..] = rest {
+                if glob_match(after, text) {
+                    return true;
+                }
+            }
+            (0..=text.len()).any(|start| glob_match(rest, &text[start..]))
+        }
+        ['*', rest @ ..] => {
+            let end = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+            (0..=end).any(|start| glob_match(rest, &text[start..]))
+        }
+        ['?', rest @ ..] => matches!(text, [c, ..] if *c != '/') && glob_match(rest, &text[1..]),
+        ['[', class @ ..] => {
+            let Some(end) = class
+                .iter()
+                .skip(1)
+                .position(|&c| c == ']')
+                .map(|end| end + 1)
+            else {
+                return matches!(text, ['[', ..]) && glob_match(class, &text[1..]);
+            };
+            let Some(&c) = text.first() else {
+                return false;
+            };
+            let (negated, set) = match class[..end] {
+                ['!' | '^', ref set @ ..] => (true, set),
+                ref set => (false, set),
+            };
+            let mut in_set = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    in_set |= set[i] <= c && c <= set[i + 2];
+                    i += 3;
+                } else {
+                    in_set |= set[i] == c;
+                    i += 1;
+                }
+            }
+
+            c != '/' && in_set != negated && glob_match(&class[end + 1..], &text[1..])
+        }
+        ['\\', c, rest @ ..] | [c, rest @ ..] => {
+            matches!(text, [t, ..] if t == c) && glob_match(rest, &text[1..])
+        }
+    }
+}
+
+#[test]
+fn glob_match_tests() {
+    let glob = |glob: &str, text: &str| {
+        glob_match(
+            &glob.chars().collect::<Vec<char>>(),
+            &text.chars().collect::<Vec<char>>(),
+        )
+    };
+
+    assert!(glob("*.md", "note.md"));
+    assert!(!glob("*.md", "folder/note.md"));
+    assert!(glob("**/*.md", "note.md"));
+    assert!(glob("**/*.md", "a/b/note.md"));
+    assert!(glob("drafts/**", "drafts/a/b.md"));
+    assert!(glob("note-?.md", "note-1.md"));
+    assert!(glob("[a-c]x", "bx"));
+    assert!(!glob("[!a-c]x", "bx"));
+    assert!(glob("\\*", "*"));
+    assert!(!glob("\\*", "a"));
+}
+
+#[test]
+fn jeff_ignore_tests() {
+    let vault = std::env::temp_dir().join(format!("jeff_ignore_{}", std::process::id()));
+    for folder in [
+        ".jeff",
+        ".git",
+        ".github",
+        "node_modules",
+        "drafts/old",
+        "notes",
+    ] {
+        std::fs::create_dir_all(vault.join(folder)).unwrap();
+    }
+
+    let ignore = JeffIgnore::load(&vault).unwrap();
+    assert!(ignore.is_ignored(&vault.join(".git")));
+    assert!(ignore.is_ignored(&vault.join(".git/config")));
+    assert!(!ignore.is_ignored(&vault.join("node_modules")));
+    assert!(!ignore.is_ignored(&vault.join(".hidden.md")));
+
+    std::fs::write(
+        vault.join(".jeffignore"),
+        "# comment\nnode_modules/\n/drafts/old\n*.tmp.md\n!.github/\n!.jeff/\n",
+    )
+    .unwrap();
+    let ignore = JeffIgnore::load(&vault).unwrap();
+    assert!(ignore.is_ignored(&vault.join("node_modules")));
+    assert!(ignore.is_ignored(&vault.join("drafts/old")));
+    assert!(!ignore.is_ignored(&vault.join("drafts")));
+    assert!(ignore.is_ignored(&vault.join("notes/a.tmp.md")));
+    assert!(!ignore.is_ignored(&vault.join("notes/a.md")));
+    assert!(!ignore.is_ignored(&vault.join(".github")));
+    assert!(ignore.is_ignored(&vault.join(".jeff/data")));
+    assert!(ignore.is_ignored(&vault.join(".git")));
+
+    let nested = JeffIgnore::for_path(&vault.join("notes")).unwrap();
+    assert!(nested.is_ignored(&vault.join("node_modules")));
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
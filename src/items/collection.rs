@@ -1,22 +1,31 @@
-use anyhow::anyhow;
-
 use crate::items::{Folder, Item, Note};
-use crate::output::error::JeffResult;
-use crate::prelude::JeffDisplay;
+use crate::output::error::{Error, JeffResult};
+use crate::prelude::ItemType;
 
 pub trait Collection: Item {
+    /// Finds a note by its name, or by its name and extension (like
+    /// `idea.txt`) when notes with different extensions share a name.
     fn get_note_with_name(&self, name: &String) -> JeffResult<Note> {
-        for note in self.notes() {
-            if &note.get_name() == name {
-                return Ok(note);
-            }
+        let notes = self.notes();
+        if let Some(note) = notes.iter().find(|note| &note.get_full_name() == name) {
+            return Ok(note.clone());
         }
 
-        Err(anyhow!(
-            "Note [{}] does not exist in [{}]",
-            name,
-            self.get_name()
-        ))
+        let mut matches: Vec<Note> = notes
+            .into_iter()
+            .filter(|note| &note.get_name() == name)
+            .collect();
+
+        match matches.len() {
+            0 => Err(Error::ItemNotFound(ItemType::Nt, name.to_owned())),
+            1 => Ok(matches.remove(0)),
+            _ => {
+                let mut full_names: Vec<String> =
+                    matches.iter().map(|note| note.get_full_name()).collect();
+                full_names.sort();
+                Err(Error::AmbiguousName(name.to_owned(), full_names))
+            }
+        }
     }
 
     fn get_folder_with_name(&self, name: &String) -> JeffResult<Folder> {
@@ -26,11 +35,7 @@ pub trait Collection: Item {
             }
         }
 
-        Err(anyhow!(
-            "Folder [{}] does not exist in [{}]",
-            name,
-            self.get_name()
-        ))
+        Err(Error::ItemNotFound(ItemType::Fd, name.to_owned()))
     }
 
     fn notes(&self) -> Vec<Note>;
@@ -51,38 +56,58 @@ pub trait Collection: Item {
         folders
     }
 
-    /**
-     * TODO: Move into [JeffDisplay] trait
-     */
-    fn list(&self) {
-        for folder in self.folders_sorted() {
-            println!("└── {}", folder.to_display_string());
-            folder.list_with_buffer("".to_string());
-        }
+    /// Returns the notes inside of the collection and its folders, in the
+    /// order of [Collection::tree].
+    fn notes_in_tree_order(&self) -> Vec<Note> {
+        let mut notes: Vec<Note> = self
+            .folders_sorted()
+            .iter()
+            .flat_map(|folder| folder.notes_in_tree_order())
+            .collect();
+        notes.extend(self.notes_sorted());
 
-        let notes = self.notes_sorted();
-        for (i, note) in notes.iter().enumerate() {
-            if i == notes.len() - 1 {
-                println!("└── {}", note.to_display_string());
-            } else {
-                println!("├── {}", note.to_display_string());
-            }
-        }
+        notes
     }
 
-    fn list_with_buffer(&self, buffer: String) {
-        for folder in self.folders_sorted() {
-            println!("{} └── {}", buffer, folder.to_display_string());
-            folder.list_with_buffer(format!("{}    ", buffer).to_string());
-        }
+    /// Returns the tree of folders and notes inside of the collection.
+    fn tree(&self) -> Vec<TreeNode> {
+        let mut nodes: Vec<TreeNode> = self
+            .folders_sorted()
+            .iter()
+            .map(|folder| TreeNode {
+                name: folder.get_name(),
+                item_type: ItemType::Fd,
+                children: folder.tree(),
+            })
+            .collect();
 
+        // notes that share a name are shown with their extensions
         let notes = self.notes_sorted();
-        for (i, note) in notes.iter().enumerate() {
-            if i == notes.len() - 1 {
-                println!("{}    └── {}", buffer, note.to_display_string());
+        let shares_name = |note: &Note| {
+            notes
+                .iter()
+                .filter(|other| other.get_name() == note.get_name())
+                .count()
+                > 1
+        };
+        nodes.extend(notes.iter().map(|note| TreeNode {
+            name: if shares_name(note) {
+                note.get_full_name()
             } else {
-                println!("{}    ├── {}", buffer, note.to_display_string());
-            }
-        }
+                note.get_name()
+            },
+            item_type: ItemType::Nt,
+            children: vec![],
+        }));
+
+        nodes
     }
 }
+
+/// A note or folder, and the items inside of it.
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub name: String,
+    pub item_type: ItemType,
+    pub children: Vec<TreeNode>,
+}
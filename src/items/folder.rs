@@ -1,8 +1,8 @@
-use anyhow::anyhow;
 use std::path::PathBuf;
 
+use crate::ignore::JeffIgnore;
 use crate::prelude::*;
-use std::fs::{create_dir, remove_dir_all, rename};
+use std::fs::{create_dir, rename};
 
 #[derive(Debug, Clone)]
 pub struct Folder {
@@ -32,9 +32,8 @@ impl Folder {
      * Creates a new folder at the given location.
      */
     pub fn create(absolute_path: PathBuf) -> JeffResult<Self> {
-        println!("{:?}", absolute_path);
         if !Folder::is_valid_path(&absolute_path) {
-            return Err(anyhow!("Invalid folder path"));
+            return Err(Error::InvalidPath(absolute_path.to_owned()));
         }
 
         let folder = Folder {
@@ -54,8 +53,15 @@ impl Folder {
      * into notes and folders.
      */
     pub fn load(absolute_path: PathBuf) -> JeffResult<Self> {
+        let ignore = JeffIgnore::for_path(&absolute_path)?;
+
+        Folder::load_ignoring(absolute_path, &ignore)
+    }
+
+    /// Like [Folder::load], leaving out the items that `ignore` ignores.
+    pub fn load_ignoring(absolute_path: PathBuf, ignore: &JeffIgnore) -> JeffResult<Self> {
         if !Folder::is_valid_path(&absolute_path) {
-            return Err(anyhow!("Invalid folder path"));
+            return Err(Error::InvalidPath(absolute_path.to_owned()));
         }
 
         let mut folder = Folder {
@@ -64,14 +70,16 @@ impl Folder {
             notes: vec![],
         };
 
-        folder.load_contents()?;
+        folder.load_contents(ignore)?;
 
         Ok(folder)
     }
 
     /**
      * Check if a given location points to a valid
-     * `jeff` [Folder]
+     * `jeff` [Folder]. `.jeff` is never a folder of
+     * the vault. Other folders can be left out with
+     * [JeffIgnore].
      */
     pub fn is_valid_path(location: &PathBuf) -> bool {
         location.file_name().unwrap() != ".jeff" && !location.is_file()
@@ -105,17 +113,6 @@ impl Item for Folder {
 
         Ok(())
     }
-
-    /**
-     * Deletes the folder and all of its contents.
-     */
-    fn delete(&self) -> JeffResult<()> {
-        // TODO: make sure the user is prompted before executing
-        // NOTE: this could potentially delete a lot of information!
-        remove_dir_all(&self.location.as_path())?;
-
-        Ok(())
-    }
 }
 
 impl Folder {
@@ -123,14 +120,18 @@ impl Folder {
      * Loads the contents of a folder into notes and folders vectors.
      * Note: Folders inside of `self` are also loaded.
      */
-    pub fn load_contents(&mut self) -> JeffResult<()> {
+    pub fn load_contents(&mut self, ignore: &JeffIgnore) -> JeffResult<()> {
         for item in self.location.read_dir().unwrap() {
             let item_location = item.unwrap().path();
 
+            if ignore.is_ignored(&item_location) {
+                continue;
+            }
+
             if Folder::is_valid_path(&item_location) {
-                let folder = Folder::load(item_location)?;
+                let folder = Folder::load_ignoring(item_location, ignore)?;
                 self.folders.push(Box::new(folder));
-            } else if Note::is_valid_path(&item_location) {
+            } else if ignore.is_note(&item_location) {
                 let note = Note::load(item_location)?;
                 self.notes.push(note);
             }
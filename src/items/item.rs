@@ -19,5 +19,4 @@ pub trait Item {
     /// `new_location` - absolute path to the new location
     fn relocate(&mut self, new_location: PathBuf) -> JeffResult<()>;
     fn rename(&mut self, new_name: String) -> JeffResult<()>;
-    fn delete(&self) -> JeffResult<()>;
 }
@@ -1,10 +1,14 @@
-use anyhow::anyhow;
 use std::path::PathBuf;
 
-use std::fs::{remove_file, rename};
+use std::fs::{read_to_string, rename, write};
 
 use crate::prelude::*;
 
+/// File extensions that notes can have. Vaults recognise `md` unless they
+/// are configured to recognise others.
+pub const NOTE_EXTENSIONS: [&str; 5] = ["md", "markdown", "txt", "org", "adoc"];
+pub const DEFAULT_NOTE_EXTENSION: &str = "md";
+
 #[derive(Debug, Clone)]
 pub struct Note {
     location: JeffPath,
@@ -12,18 +16,37 @@ pub struct Note {
 
 impl Note {
     pub fn generate_abs_path(parent_dir: &PathBuf, note_name: &String) -> PathBuf {
+        Note::generate_abs_path_with_extension(parent_dir, note_name, DEFAULT_NOTE_EXTENSION)
+    }
+
+    pub fn generate_abs_path_with_extension(
+        parent_dir: &PathBuf,
+        note_name: &String,
+        extension: &str,
+    ) -> PathBuf {
         join_paths(vec![
             parent_dir.to_str().unwrap(),
-            format!("{}.md", note_name).as_str(),
+            format!("{}.{}", note_name, extension).as_str(),
         ])
     }
 
+    /// Splits a name like `idea.txt` into `idea` and `txt`, if it ends with
+    /// a note extension.
+    pub fn split_extension(name: &str) -> (&str, Option<&str>) {
+        match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() && NOTE_EXTENSIONS.contains(&extension) => {
+                (stem, Some(extension))
+            }
+            _ => (name, None),
+        }
+    }
+
     /**
      * Initializes an existing note from its path
      */
     pub fn load(note_location: PathBuf) -> JeffResult<Self> {
         if !Note::is_valid_path(&note_location) {
-            return Err(anyhow!("Invalid note path [{:?}]", note_location));
+            return Err(Error::InvalidPath(note_location.to_owned()));
         }
 
         Ok(Note {
@@ -33,7 +56,7 @@ impl Note {
 
     pub fn create(note_location: PathBuf) -> JeffResult<Self> {
         if !Note::is_valid_path(&note_location) {
-            return Err(anyhow!("Invalid note path [{:?}]", note_location));
+            return Err(Error::InvalidPath(note_location.to_owned()));
         }
 
         create_file(note_location.clone())?;
@@ -46,11 +69,51 @@ impl Note {
      * Checks if a path points to a valid [Note].
      */
     pub fn is_valid_path(absolute_path: &PathBuf) -> bool {
-        if absolute_path.extension().is_none() {
+        let Some(extension) = absolute_path.extension() else {
             return false;
-        }
+        };
 
-        !absolute_path.is_dir() && absolute_path.extension().unwrap() == "md"
+        !absolute_path.is_dir()
+            && NOTE_EXTENSIONS
+                .iter()
+                .any(|note_extension| extension == *note_extension)
+    }
+
+    /// The note's file extension, e.g. `md`.
+    pub fn extension(&self) -> String {
+        self.location
+            .extension()
+            .map_or(String::new(), |extension| {
+                extension.to_string_lossy().to_string()
+            })
+    }
+
+    /// Reads the contents of the note.
+    pub fn read(&self) -> JeffResult<String> {
+        Ok(read_to_string(self.location.as_path())?)
+    }
+
+    /// Overwrites the contents of the note.
+    pub fn write(&self, contents: &str) -> JeffResult<()> {
+        write(self.location.as_path(), contents)?;
+
+        Ok(())
+    }
+
+    /// Appends text to the end of the note, or to the end of the section
+    /// under `heading`.
+    pub fn append(&self, text: &str, heading: Option<&str>) -> JeffResult<()> {
+        let contents = append_text(&self.read()?, text, heading)?;
+
+        self.write(&contents)
+    }
+
+    /// Prepends text to the start of the note, or to the start of the section
+    /// under `heading`.
+    pub fn prepend(&self, text: &str, heading: Option<&str>) -> JeffResult<()> {
+        let contents = prepend_text(&self.read()?, text, heading)?;
+
+        self.write(&contents)
     }
 }
 
@@ -67,20 +130,19 @@ impl Item for Note {
         Ok(())
     }
 
+    /// Keeps the note's extension, unless `new_name` ends with another one.
     fn rename(&mut self, new_name: String) -> JeffResult<()> {
-        let new_location = JeffPath::from_parent(&self.location.parent(), new_name);
+        let (new_name, extension) = match Note::split_extension(&new_name) {
+            (stem, Some(extension)) => (stem.to_string(), extension.to_string()),
+            (name, None) => (name.to_string(), self.extension()),
+        };
+        let new_location: JeffPath =
+            Note::generate_abs_path_with_extension(&self.location.parent(), &new_name, &extension)
+                .into();
 
         rename(&self.location.as_path(), &new_location.as_path())?;
         self.location = new_location.into();
 
         Ok(())
     }
-
-    fn delete(&self) -> JeffResult<()> {
-        // TODO: make sure the user is prompted before executing
-        // NOTE: this could potentially delete a lot of information!
-        remove_file(&self.location.as_path())?;
-
-        Ok(())
-    }
 }
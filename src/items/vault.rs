@@ -1,9 +1,9 @@
-use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::{remove_dir_all, rename};
-use std::path::PathBuf;
+use std::fs::rename;
+use std::path::{Path, PathBuf};
 
+use crate::ignore::JeffIgnore;
 use crate::prelude::*;
 
 #[derive(Debug, Clone)]
@@ -39,16 +39,14 @@ impl Vault {
     pub fn create(absolute_path: PathBuf) -> JeffResult<Self> {
         let path: JeffPath = absolute_path.to_owned().into();
         if path.exists() {
-            return Err(anyhow!(VaultAlreadyExists(
-                "Vault already exists".to_string()
-            )));
+            return Err(VaultAlreadyExists(path.file_name()));
         }
 
         std::fs::create_dir(&absolute_path.as_path())?;
 
         let store_path = JeffPath::from_parent(&path, ".jeff/data".to_string()).to_path_buf();
-        let mut new_store = VaultStore::load_path(store_path.clone());
-        new_store.set_absolute_path(store_path);
+        let mut new_store = VaultStore::load_path(store_path.clone())?;
+        new_store.set_absolute_path(store_path)?;
 
         let new_vault = Vault {
             path: path.to_owned(),
@@ -65,6 +63,21 @@ impl Vault {
         Ok(new_vault)
     }
 
+    /**
+     * Turns an existing folder into a vault, by adding jeff's data to it.
+     */
+    pub fn import(absolute_path: PathBuf) -> JeffResult<Self> {
+        if !Vault::is_valid_path(&absolute_path) {
+            return Err(Error::InvalidPath(absolute_path));
+        }
+
+        let store_path = absolute_path.join(".jeff/data");
+        let mut store = VaultStore::load_path(store_path.clone())?;
+        store.set_absolute_path(store_path)?;
+
+        Vault::load(absolute_path)
+    }
+
     /**
      * Initializes an existing folder and loads it's contents
      * into notes and folders.
@@ -78,7 +91,7 @@ impl Vault {
             notes: vec![],
             vault_store: VaultStore::load_path(
                 JeffPath::from_parent(&path, ".jeff/data".to_string()).to_path_buf(),
-            ),
+            )?,
         };
 
         new_vault.load_contents()?;
@@ -86,13 +99,23 @@ impl Vault {
         Ok(new_vault)
     }
 
+    /// Returns the root of the vault that contains `path`, if any.
+    pub fn root_of(path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .find(|path| path.join(".jeff").is_dir())
+            .map(Path::to_path_buf)
+    }
+
     /**
-     * Check if a given absolute path is a valid `jeff` [Vault]
+     * Check if a given absolute path is a valid `jeff` [Vault].
+     * Vaults inside of other vaults are refused by [Vaults], which knows
+     * where the other vaults are.
      */
     fn is_valid_path(absolute_path: &PathBuf) -> bool {
-        // TOOD: add check to ensure that this vault
-        // is not inside of another vault
-        !absolute_path.is_file() && absolute_path.file_name().unwrap() != ".jeff"
+        !absolute_path.is_file()
+            && absolute_path
+                .file_name()
+                .is_some_and(|name| name != ".jeff")
     }
 }
 
@@ -105,7 +128,8 @@ impl Item for Vault {
         assert!(Vault::is_valid_path(&new_absolute_path));
         rename(&self.path.as_path(), &new_absolute_path)?;
         self.path = new_absolute_path.to_owned().into();
-        self.vault_store.set_absolute_path(new_absolute_path);
+        self.vault_store
+            .set_absolute_path(new_absolute_path.join(".jeff/data"))?;
 
         Ok(())
     }
@@ -117,15 +141,8 @@ impl Item for Vault {
         assert!(Vault::is_valid_path(&new_absolute_path));
         rename(&self.path.as_path(), &new_absolute_path)?;
         self.path = new_absolute_path.to_owned().into();
-        self.vault_store.set_absolute_path(new_absolute_path);
-
-        Ok(())
-    }
-
-    fn delete(&self) -> JeffResult<()> {
-        // TODO: make sure the user is prompted before executing
-        // NOTE: this could potentially delete a lot of information!
-        remove_dir_all(&self.path.as_path())?;
+        self.vault_store
+            .set_absolute_path(new_absolute_path.join(".jeff/data"))?;
 
         Ok(())
     }
@@ -156,20 +173,41 @@ impl Vault {
         }
     }
 
+    /// Returns a folder with the given name from the active folder. If the active
+    /// folder does not contain it, the root of the vault is searched.
+    pub fn get_folder_from_active_folder(&self, name: &String) -> JeffResult<Folder> {
+        if let Ok(Some(active_folder)) = self.get_active_folder() {
+            if let Ok(folder) = active_folder.get_folder_with_name(name) {
+                return Ok(folder);
+            }
+        }
+
+        self.get_folder_with_name(name)
+    }
+
     pub fn get_active_folder(&self) -> JeffResult<Option<Folder>> {
-        if let Some(active_folder_path) = self.get_active_folder_path() {
-            /*
-             * We use active_folder_path (the relative path to the active folder) as
-             * the name of the folder here in get_folder_with_name because the relative
-             * path is just the name of the folder.
-             *
-             * This can, and should, be improved.
-             */
-            let active_folder = self.get_folder_with_name(&active_folder_path)?;
-            Ok(Some(active_folder))
-        } else {
-            Ok(None)
+        let active_folder_path = match self.get_active_folder_path() {
+            Some(path) if !path.is_empty() => path,
+            _ => return Ok(None),
+        };
+
+        // Walk down from the root of the vault, one folder at a time.
+        let mut names = PathBuf::from(active_folder_path)
+            .iter()
+            .map(|name| name.to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+            .into_iter();
+        let mut active_folder = self.get_folder_with_name(&names.next().unwrap())?;
+        for name in names {
+            active_folder = active_folder.get_folder_with_name(&name)?;
         }
+
+        Ok(Some(active_folder))
+    }
+
+    /// Sets the active folder, given as a path relative to the vault.
+    pub fn set_active_folder(&mut self, folder_path: Option<String>) -> JeffResult<()> {
+        self.vault_store.set_folder_path(folder_path)
     }
 
     /// Returns the path absolute path to the folder inside of the vault that
@@ -189,13 +227,18 @@ impl Vault {
      * Note: Folders inside of `self` are also loaded.
      */
     pub fn load_contents(&mut self) -> JeffResult<()> {
+        let ignore = JeffIgnore::load(&self.path)?;
         for item in self.path.read_dir().unwrap() {
             let item_location = item.unwrap().path();
 
+            if ignore.is_ignored(&item_location) {
+                continue;
+            }
+
             if Folder::is_valid_path(&item_location) {
-                let folder = Folder::load(item_location)?;
+                let folder = Folder::load_ignoring(item_location, &ignore)?;
                 self.folders.push(folder);
-            } else if Note::is_valid_path(&item_location) {
+            } else if ignore.is_note(&item_location) {
                 let note = Note::load(item_location)?;
                 self.notes.push(note);
             }
@@ -218,11 +261,11 @@ impl Vault {
         };
 
         if !new_location.exists() {
-            return Err(anyhow!(Error::PathNotFound));
+            return Err(Error::PathNotFound);
         }
 
         if !new_location.starts_with(&vault_path.as_path()) {
-            return Err(anyhow!(Error::OutOfBounds));
+            return Err(Error::OutOfBounds);
         }
 
         let mut destination_folder = new_location.strip_prefix(vault_path.as_path()).unwrap();
@@ -231,19 +274,45 @@ impl Vault {
         }
         let destination_folder = destination_folder.to_path_buf();
 
+        // ignored folders aren't part of the vault
+        if JeffIgnore::load(vault_path)?.is_ignored(&new_location) {
+            return Err(Error::PathNotFound);
+        }
+
         self.vault_store
-            .set_folder_path(Some(path_to_string(destination_folder)));
+            .set_folder_path(Some(path_to_string(destination_folder)))?;
 
         Ok(())
     }
 
-    fn get_active_folder_path(&self) -> Option<String> {
+    pub fn get_active_folder_path(&self) -> Option<String> {
         self.vault_store.get_folder_path()
     }
+
+    pub fn set_git_autocommit(&mut self, autocommit: bool) -> JeffResult<()> {
+        self.vault_store.set_git_autocommit(autocommit)
+    }
+
+    pub fn set_note_extensions(&mut self, extensions: Vec<String>) -> JeffResult<()> {
+        self.vault_store.set_note_extensions(extensions)
+    }
+
+    pub fn set_default_note_extension(&mut self, extension: String) -> JeffResult<()> {
+        self.vault_store.set_default_note_extension(extension)
+    }
+
+    pub fn note_extensions(&self) -> Vec<String> {
+        self.vault_store.note_extensions()
+    }
+
+    pub fn default_note_extension(&self) -> String {
+        self.vault_store.default_note_extension()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultStore {
+    schema_version: u32,
     /// relative path from this vault to the active folder
     current_folder: Option<String>,
     /// absolute path to the vault store data (some `<vault-path>/.jeff/data`)
@@ -251,19 +320,52 @@ pub struct VaultStore {
     location: Option<PathBuf>,
     /// aliases for notes inside of the vault
     aliases: HashMap<String, String>,
+    #[serde(default)]
+    git: GitSettings,
+    #[serde(default)]
+    notes: NoteSettings,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitSettings {
+    /// whether changes made by jeff are committed to the vault's repository
+    autocommit: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteSettings {
+    /// extensions of the files that are notes, out of [NOTE_EXTENSIONS]
+    extensions: Vec<String>,
+    /// extension of new notes
+    default_extension: String,
+}
+
+impl Default for NoteSettings {
+    fn default() -> Self {
+        NoteSettings {
+            extensions: vec![DEFAULT_NOTE_EXTENSION.to_string()],
+            default_extension: DEFAULT_NOTE_EXTENSION.to_string(),
+        }
+    }
 }
 
 impl Default for VaultStore {
     fn default() -> Self {
         VaultStore {
+            schema_version: VaultStore::SCHEMA_VERSION,
             current_folder: None,
             aliases: HashMap::new(),
             location: None,
+            git: GitSettings::default(),
+            notes: NoteSettings::default(),
         }
     }
 }
 
 impl FileIO for VaultStore {
+    const SCHEMA_VERSION: u32 = 2;
+
     /**
      * Path to the vault's persistent data
      * store.
@@ -271,23 +373,107 @@ impl FileIO for VaultStore {
     fn path(&self) -> PathBuf {
         self.location.clone().unwrap()
     }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            // 0 -> 1: added schema_version
+            |_| (),
+            // 1 -> 2: added git and notes settings, which have defaults
+            |_| (),
+        ]
+    }
 }
 
 impl VaultStore {
     /**
      * Updates the absolute path to the vault.
      */
-    pub fn set_absolute_path(&mut self, vault_path: PathBuf) {
+    pub fn set_absolute_path(&mut self, vault_path: PathBuf) -> JeffResult<()> {
         self.location = Some(vault_path);
-        self.store();
+        let location = self.location.clone();
+        self.update(|store| store.location = location)
     }
 
-    pub fn set_folder_path(&mut self, folder_path: Option<String>) {
-        self.current_folder = folder_path;
-        self.store();
+    pub fn set_folder_path(&mut self, folder_path: Option<String>) -> JeffResult<()> {
+        // the location isn't taken from the file, it may be out of date
+        let location = self.location.clone();
+        self.update(|store| {
+            store.location = location;
+            store.current_folder = folder_path;
+        })
     }
 
     pub fn get_folder_path(&self) -> Option<String> {
         self.current_folder.clone()
     }
+
+    pub fn set_git_autocommit(&mut self, autocommit: bool) -> JeffResult<()> {
+        let location = self.location.clone();
+        self.update(|store| {
+            store.location = location;
+            store.git.autocommit = autocommit;
+        })
+    }
+
+    pub fn git_autocommit(&self) -> bool {
+        self.git.autocommit
+    }
+
+    /// Sets the extensions of the files that are notes. If the default
+    /// extension isn't one of them, the first one becomes the default.
+    pub fn set_note_extensions(&mut self, extensions: Vec<String>) -> JeffResult<()> {
+        let mut valid_extensions: Vec<String> = vec![];
+        for extension in extensions {
+            let extension = VaultStore::note_extension(&extension)?;
+            if !valid_extensions.contains(&extension) {
+                valid_extensions.push(extension);
+            }
+        }
+        if valid_extensions.is_empty() {
+            return Err(Error::MissingValue("note.extensions".to_string()));
+        }
+
+        let location = self.location.clone();
+        self.update(|store| {
+            store.location = location;
+            if !valid_extensions.contains(&store.notes.default_extension) {
+                store.notes.default_extension = valid_extensions[0].to_owned();
+            }
+            store.notes.extensions = valid_extensions;
+        })
+    }
+
+    /// Sets the extension of new notes, and recognises it if it wasn't
+    /// already.
+    pub fn set_default_note_extension(&mut self, extension: String) -> JeffResult<()> {
+        let extension = VaultStore::note_extension(&extension)?;
+
+        let location = self.location.clone();
+        self.update(|store| {
+            store.location = location;
+            if !store.notes.extensions.contains(&extension) {
+                store.notes.extensions.push(extension.to_owned());
+            }
+            store.notes.default_extension = extension;
+        })
+    }
+
+    pub fn note_extensions(&self) -> Vec<String> {
+        self.notes.extensions.clone()
+    }
+
+    pub fn default_note_extension(&self) -> String {
+        self.notes.default_extension.clone()
+    }
+
+    /// Checks that `extension` (with or without a leading `.`) is one of
+    /// [NOTE_EXTENSIONS].
+    fn note_extension(extension: &str) -> JeffResult<String> {
+        let extension = extension.trim().trim_start_matches('.').to_lowercase();
+        if NOTE_EXTENSIONS.contains(&extension.as_str()) {
+            Ok(extension)
+        } else {
+            Err(Error::UnsupportedNoteExtension(extension))
+        }
+    }
 }
@@ -1,31 +1,46 @@
 mod app;
+mod archive;
+mod attachments;
+mod doctor;
 mod editor;
 mod enums;
+mod export;
 mod fileio;
+mod git;
+mod ignore;
 mod items;
 mod jeff_path;
+mod markdown;
+mod obsidian;
 mod output;
 mod prelude;
+mod revisions;
+mod shell;
 mod state;
 mod tests;
+mod tui;
 mod utils;
 
 #[macro_use]
 extern crate lazy_static;
 
-use crate::{
-    app::App,
-    output::{Message, Output},
-};
+use crate::{app::App, output::display_result, utils::Prompts};
 use clap::Parser;
 
 fn main() {
-    let mut app = App::new().unwrap();
-    let command = crate::state::Args::parse().command;
+    let args = crate::state::Args::parse();
+    let result = args
+        .command
+        .check_format(args.format)
+        .and_then(|_| App::with_prompts(Prompts::new(args.yes, args.no_input)))
+        .and_then(|mut app| {
+            let words: Vec<String> = std::env::args().skip(1).collect();
+            let command_line = std::iter::once("jf").chain(words.iter().map(|word| word.as_str()));
+            if let Ok(command_line) = shlex::try_join(command_line) {
+                app.set_command_line(command_line);
+            }
+            app.handle_command(args.command)
+        });
 
-    match app.handle_command(command) {
-        Ok(Message::Empty) => (),
-        Ok(message) => println!("{}", Output::Message(message)),
-        Err(message) => println!("{}", message),
-    }
+    std::process::exit(display_result(result, args.format));
 }
@@ -0,0 +1,672 @@
+/**
+ * Helpers for reading and editing the markdown contents of notes.
+ */
+use crate::prelude::*;
+
+/// Returns the heading level of a line (the number of leading `#`s),
+/// or `None` if the line is not a heading.
+pub fn heading_level(line: &str) -> Option<usize> {
+    let line = line.trim_start();
+    let level = line.chars().take_while(|char| *char == '#').count();
+
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    match line[level..].chars().next() {
+        None => Some(level),
+        Some(char) if char.is_whitespace() => Some(level),
+        _ => None,
+    }
+}
+
+/// Checks if a line is the given heading. `heading` can be the full heading
+/// line (`## Tasks`) or just its text (`Tasks`).
+fn is_heading(line: &str, heading: &str) -> bool {
+    let heading = heading.trim();
+
+    if heading_level(line).is_none() {
+        return false;
+    }
+
+    if heading.starts_with('#') {
+        line.trim() == heading
+    } else {
+        line.trim().trim_start_matches('#').trim() == heading
+    }
+}
+
+/// Tracks whether lines are inside of a code block, fenced by ``` or ~~~.
+#[derive(Default)]
+struct CodeBlocks {
+    /// the fence of the open code block
+    fence: Option<&'static str>,
+}
+
+impl CodeBlocks {
+    /// Returns whether `line` is code, counting the fences themselves. Lines
+    /// must be given in order.
+    fn is_code(&mut self, line: &str) -> bool {
+        let line = line.trim_start();
+        for fence in ["```", "~~~"] {
+            if !line.starts_with(fence) {
+                continue;
+            }
+            match self.fence {
+                None => self.fence = Some(fence),
+                Some(open) if open == fence => self.fence = None,
+                Some(_) => continue,
+            }
+            return true;
+        }
+
+        self.fence.is_some()
+    }
+}
+
+/// Returns the index of the first line after the note's frontmatter,
+/// or `0` if the note has no frontmatter.
+pub fn frontmatter_end(lines: &[&str]) -> usize {
+    if lines.first().map(|line| line.trim_end()) != Some("---") {
+        return 0;
+    }
+
+    lines
+        .iter()
+        .skip(1)
+        .position(|line| line.trim_end() == "---")
+        .map_or(0, |end| end + 2)
+}
+
+/// Returns the line range `(heading, end)` of the section under the given
+/// heading. `end` is the index of the next heading of the same or higher
+/// level, or the number of lines. Headings inside code blocks are ignored.
+fn find_section(lines: &[&str], heading: &str) -> Option<(usize, usize)> {
+    let mut code_blocks = CodeBlocks::default();
+    let mut section: Option<(usize, usize)> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        if code_blocks.is_code(line) {
+            continue;
+        }
+
+        match (section, heading_level(line)) {
+            (None, Some(level)) if is_heading(line, heading) => section = Some((i, level)),
+            (Some((start, level)), Some(next_level)) if next_level <= level => {
+                return Some((start, i));
+            }
+            _ => (),
+        }
+    }
+
+    section.map(|(start, _)| (start, lines.len()))
+}
+
+/// Joins lines with the line ending that `contents` uses, `\r\n` or `\n`.
+fn join_lines(contents: &str, lines: Vec<&str>) -> String {
+    let line_ending = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut joined = lines.join(line_ending);
+    joined.push_str(line_ending);
+
+    joined
+}
+
+/// Appends `text` to the end of `contents`, or to the end of the section
+/// under `heading` when one is given.
+pub fn append_text(contents: &str, text: &str, heading: Option<&str>) -> JeffResult<String> {
+    let mut lines: Vec<&str> = contents.lines().collect();
+
+    let insert_at = if let Some(heading) = heading {
+        let (start, end) = find_section(&lines, heading)
+            .ok_or_else(|| Error::SectionNotFound(heading.to_owned()))?;
+
+        // Insert after the last non-empty line of the section so that
+        // the spacing before the next heading is kept.
+        (start + 1..end)
+            .rev()
+            .find(|i| !lines[*i].trim().is_empty())
+            .map_or(start + 1, |i| i + 1)
+    } else {
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        lines.len()
+    };
+
+    lines.splice(insert_at..insert_at, text.lines());
+
+    Ok(join_lines(contents, lines))
+}
+
+/// Prepends `text` to the start of `contents` (after any frontmatter), or to
+/// the start of the section under `heading` when one is given.
+pub fn prepend_text(contents: &str, text: &str, heading: Option<&str>) -> JeffResult<String> {
+    let mut lines: Vec<&str> = contents.lines().collect();
+
+    let insert_at = if let Some(heading) = heading {
+        let (start, _) = find_section(&lines, heading)
+            .ok_or_else(|| Error::SectionNotFound(heading.to_owned()))?;
+        start + 1
+    } else {
+        frontmatter_end(&lines)
+    };
+
+    lines.splice(insert_at..insert_at, text.lines());
+
+    Ok(join_lines(contents, lines))
+}
+
+/// A link or an embed inside of a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// the linked note or file, without a `#heading` or `|alias`
+    pub target: String,
+    /// `![[...]]` or `![...](...)`
+    pub embed: bool,
+    /// `[[...]]` rather than `[...](...)`
+    pub wiki: bool,
+}
+
+/// Returns the wiki and markdown links in `contents`. Links inside of code,
+/// links to headings of the same note, and links to URLs are left out.
+pub fn links(contents: &str) -> Vec<Link> {
+    let mut links = vec![];
+    let mut code_blocks = CodeBlocks::default();
+
+    for line in contents.lines() {
+        if code_blocks.is_code(line) {
+            continue;
+        }
+
+        // odd parts are inline code
+        for text in line.split('`').step_by(2) {
+            links.extend(line_links(text));
+        }
+    }
+
+    links
+}
+
+fn line_links(text: &str) -> Vec<Link> {
+    let mut links = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        let embed = rest[..start].ends_with('!');
+        let after = &rest[start..];
+
+        let (link, length) = if let Some(inner) = after.strip_prefix("[[") {
+            let Some(end) = inner.find("]]") else {
+                break;
+            };
+            let target = inner[..end].split(['|', '#']).next().unwrap_or_default();
+            (Some((target.trim().to_string(), true)), end + 4)
+        } else if let Some((label_end, target_end)) = markdown_link_bounds(after) {
+            let target = markdown_link_target(&after[label_end + 2..target_end]);
+            (target.map(|target| (target, false)), target_end + 1)
+        } else {
+            (None, 1)
+        };
+
+        if let Some((target, wiki)) = link.filter(|(target, _)| !target.is_empty()) {
+            links.push(Link {
+                target,
+                embed,
+                wiki,
+            });
+        }
+        rest = &after[length..];
+    }
+
+    links
+}
+
+/// Returns the positions of the `]` that ends the label and the `)` that
+/// ends the target of a markdown link at the start of `text`.
+fn markdown_link_bounds(text: &str) -> Option<(usize, usize)> {
+    let label_end = text.find("](")?;
+    if text[1..label_end].contains('[') {
+        return None;
+    }
+    let target_end = label_end + 2 + text[label_end + 2..].find(')')?;
+
+    Some((label_end, target_end))
+}
+
+/// Returns the decoded path of a markdown link target like `<a b.png>`,
+/// `a%20b.png "title"` or `note.md#heading`, or `None` for URLs.
+fn markdown_link_target(target: &str) -> Option<String> {
+    let target = target.trim();
+    let target = match target.strip_prefix('<') {
+        Some(target) => target.split('>').next().unwrap_or_default(),
+        None => target.split_whitespace().next().unwrap_or_default(),
+    };
+    let target = target.split('#').next().unwrap_or_default();
+
+    if is_url(target) {
+        return None;
+    }
+
+    Some(percent_decode(target))
+}
+
+/// Whether a link target is a URL, like `https://...` or `mailto:...`,
+/// rather than a path. Windows drive letters aren't schemes.
+pub fn is_url(target: &str) -> bool {
+    let scheme = target.split(':').next().unwrap_or_default();
+
+    target.contains(':')
+        && scheme.len() > 1
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+}
+
+/// Decodes `%20`-style escapes.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Escapes the characters of a path that would end a markdown link target.
+pub fn percent_encode(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/**
+ * Replaces the wiki links in `contents`, leaving out code. `replace` is
+ * given the target and alias of each link, and whether it is an embed, and
+ * returns its replacement, or `None` to keep the link.
+ */
+pub fn replace_wiki_links<F>(contents: &str, replace: F) -> String
+where
+    F: Fn(&str, Option<&str>, bool) -> Option<String>,
+{
+    let mut lines = vec![];
+    let mut code_blocks = CodeBlocks::default();
+
+    for line in contents.lines() {
+        if code_blocks.is_code(line) {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        // odd parts are inline code
+        let parts: Vec<String> = line
+            .split('`')
+            .enumerate()
+            .map(|(i, text)| match i % 2 {
+                0 => line_replace_wiki_links(text, &replace),
+                _ => text.to_string(),
+            })
+            .collect();
+        lines.push(parts.join("`"));
+    }
+
+    let mut replaced = lines.join("\n");
+    if contents.ends_with('\n') {
+        replaced.push('\n');
+    }
+
+    replaced
+}
+
+fn line_replace_wiki_links<F>(text: &str, replace: &F) -> String
+where
+    F: Fn(&str, Option<&str>, bool) -> Option<String>,
+{
+    let mut replaced = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let Some(end) = rest[start..].find("]]").map(|end| start + end) else {
+            break;
+        };
+        let embed = rest[..start].ends_with('!');
+        let (target, alias) = match rest[start + 2..end].split_once('|') {
+            Some((target, alias)) => (target.trim(), Some(alias.trim())),
+            None => (rest[start + 2..end].trim(), None),
+        };
+
+        match replace(target, alias, embed) {
+            Some(replacement) => {
+                let link_start = if embed { start - 1 } else { start };
+                replaced.push_str(&rest[..link_start]);
+                replaced.push_str(&replacement);
+            }
+            None => replaced.push_str(&rest[..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    replaced.push_str(rest);
+
+    replaced
+}
+
+/// Returns the anchor of a heading, like GitHub makes them: `Weekly
+/// review!` becomes `weekly-review`.
+pub fn heading_anchor(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|char| match char {
+            ' ' => Some('-'),
+            char if char.is_alphanumeric() || char == '-' || char == '_' => Some(char),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A frontmatter value: `key: value`, or a list written as `key: [a, b]` or
+/// as `- a` lines under the key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrontmatterValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+/// Returns the `key: value` pairs of the note's frontmatter, in order.
+/// Nested values aren't supported, their keys are read as top level keys.
+pub fn frontmatter(contents: &str) -> Vec<(String, FrontmatterValue)> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let end = frontmatter_end(&lines);
+    let mut values: Vec<(String, FrontmatterValue)> = vec![];
+
+    for line in lines.get(1..end.saturating_sub(1)).unwrap_or_default() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(item) = line.strip_prefix("- ") {
+            match values.last_mut() {
+                Some((_, FrontmatterValue::List(items))) => items.push(unquote(item)),
+                Some((_, value)) if *value == FrontmatterValue::Text(String::new()) => {
+                    *value = FrontmatterValue::List(vec![unquote(item)]);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = match value
+            .strip_prefix('[')
+            .and_then(|value| value.strip_suffix(']'))
+        {
+            Some(items) => FrontmatterValue::List(
+                items
+                    .split(',')
+                    .map(unquote)
+                    .filter(|item| !item.is_empty())
+                    .collect(),
+            ),
+            None => FrontmatterValue::Text(unquote(value)),
+        };
+        values.push((key.trim().to_string(), value));
+    }
+
+    values
+}
+
+/// Removes the quotes around a frontmatter value.
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(text) = text
+            .strip_prefix(quote)
+            .and_then(|text| text.strip_suffix(quote))
+        {
+            return text.to_string();
+        }
+    }
+
+    text.to_string()
+}
+
+/// Returns the tags of a note, sorted: the ones in the `tags` of its
+/// frontmatter, and the `#tags` in its text, leaving out code and headings.
+pub fn tags(contents: &str) -> Vec<String> {
+    let mut tags = vec![];
+    for (key, value) in frontmatter(contents) {
+        if key != "tags" && key != "tag" {
+            continue;
+        }
+        let values = match value {
+            FrontmatterValue::Text(text) => text.split([',', ' ']).map(str::to_string).collect(),
+            FrontmatterValue::List(items) => items,
+        };
+        tags.extend(
+            values
+                .iter()
+                .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                .filter(|tag| is_tag(tag)),
+        );
+    }
+
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut code_blocks = CodeBlocks::default();
+    for line in &lines[frontmatter_end(&lines)..] {
+        if code_blocks.is_code(line) || heading_level(line).is_some() {
+            continue;
+        }
+
+        // odd parts are inline code
+        for text in line.split('`').step_by(2) {
+            tags.extend(line_tags(text));
+        }
+    }
+
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/**
+ * Whether `tag` can be a tag. Tags are made of letters, digits and `-_/`,
+ * can't be only digits, and `/` separates nested tags, which can't be empty.
+ * Tags name the pages of an exported website, so they can't be paths like
+ * `../index` either.
+ */
+fn is_tag(tag: &str) -> bool {
+    tag.chars()
+        .all(|char| char.is_alphanumeric() || "-_/".contains(char))
+        && tag
+            .chars()
+            .any(|char| !char.is_ascii_digit() && char != '/')
+        && tag
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+/// Returns the `#tags` in `text`. Tags start after whitespace, and can't be
+/// only digits, like `#1`.
+fn line_tags(text: &str) -> Vec<String> {
+    let mut tags = vec![];
+    let mut previous = ' ';
+
+    for (i, char) in text.char_indices() {
+        if char == '#' && previous.is_whitespace() {
+            let tag: String = text[i + 1..]
+                .chars()
+                .take_while(|char| char.is_alphanumeric() || "-_/".contains(*char))
+                .collect();
+            let tag = tag.trim_end_matches('/');
+            if is_tag(tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        previous = char;
+    }
+
+    tags
+}
+
+#[test]
+fn append_and_prepend_tests() {
+    let note = "---\ntags: daily\n---\n# Today\n## Tasks\n- a\n\n## Log\nstarted\n";
+
+    assert_eq!(
+        "---\ntags: daily\n---\n# Today\n## Tasks\n- a\n\n## Log\nstarted\nfinished\n",
+        append_text(note, "finished", None).unwrap()
+    );
+    assert_eq!(
+        "---\ntags: daily\n---\n# Today\n## Tasks\n- a\n- b\n\n## Log\nstarted\n",
+        append_text(note, "- b", Some("## Tasks")).unwrap()
+    );
+    assert_eq!(
+        "---\ntags: daily\n---\n# Today\n## Tasks\n- a\n\n## Log\nstarted\nfinished\n",
+        append_text(note, "finished", Some("Log")).unwrap()
+    );
+    assert_eq!(
+        "---\ntags: daily\n---\nfirst\n# Today\n## Tasks\n- a\n\n## Log\nstarted\n",
+        prepend_text(note, "first", None).unwrap()
+    );
+    assert_eq!(
+        "---\ntags: daily\n---\n# Today\n## Tasks\n- z\n- a\n\n## Log\nstarted\n",
+        prepend_text(note, "- z", Some("## Tasks")).unwrap()
+    );
+    assert!(append_text(note, "text", Some("## Missing")).is_err());
+    assert!(append_text("```\n## Tasks\n```\n", "text", Some("## Tasks")).is_err());
+    assert!(append_text("~~~\n## Tasks\n~~~\n", "text", Some("## Tasks")).is_err());
+    assert_eq!(
+        "## Tasks\n~~~\n# code\n```\n~~~\n- b\n## Log\n",
+        append_text(
+            "## Tasks\n~~~\n# code\n```\n~~~\n## Log\n",
+            "- b",
+            Some("Tasks")
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        "# Today\r\n## Tasks\r\n- a\r\n- b\r\n",
+        append_text("# Today\r\n## Tasks\r\n- a\r\n", "- b", Some("Tasks")).unwrap()
+    );
+    assert_eq!(
+        "first\r\n# Today\r\n",
+        prepend_text("# Today\r\n", "first", None).unwrap()
+    );
+    assert_eq!("text\n", append_text("", "text", None).unwrap());
+}
+
+#[test]
+fn links_tests() {
+    let link = |target: &str, embed: bool, wiki: bool| Link {
+        target: target.to_string(),
+        embed,
+        wiki,
+    };
+    let note = "\
+See [[other note|alias]] and ![[image.png]].
+![photo](attachments/my%20photo.jpg \"title\") [site](https://example.com)
+[heading](#tasks) [[#tasks]] `[[code]]` [a](<b c.md#d>)
+```
+![[in a code block.png]]
+```
+[[folder/note#heading]]
+";
+
+    assert_eq!(
+        vec![
+            link("other note", false, true),
+            link("image.png", true, true),
+            link("attachments/my photo.jpg", true, false),
+            link("b c.md", false, false),
+            link("folder/note", false, true),
+        ],
+        links(note)
+    );
+    assert_eq!("a%20%28b%29%25", percent_encode("a (b)%"));
+    assert_eq!("a (b)%", percent_decode(&percent_encode("a (b)%")));
+}
+
+#[test]
+fn frontmatter_and_tags_tests() {
+    let note = "\
+---
+title: \"Weekly review\"
+tags: [work, 'planning']
+aliases:
+  - review
+---
+# Review #heading
+Went well #good-news, see [[#tasks]] and #2.
+`#code` #projects/jeff
+```
+#in-a-code-block
+```
+";
+
+    assert_eq!(
+        vec![
+            (
+                "title".to_string(),
+                FrontmatterValue::Text("Weekly review".to_string())
+            ),
+            (
+                "tags".to_string(),
+                FrontmatterValue::List(vec!["work".to_string(), "planning".to_string()])
+            ),
+            (
+                "aliases".to_string(),
+                FrontmatterValue::List(vec!["review".to_string()])
+            ),
+        ],
+        frontmatter(note)
+    );
+    assert_eq!(
+        vec!["good-news", "planning", "projects/jeff", "work"],
+        tags(note)
+    );
+    assert!(frontmatter("no frontmatter").is_empty());
+
+    let paths = "---\ntags: [../../../pwned, ../index, /etc, a//b, ok, 2024]\n---\n#a//b #/etc";
+    assert_eq!(vec!["ok"], tags(paths));
+}
+
+#[test]
+fn replace_wiki_links_tests() {
+    let note =
+        "See [[plan|the plan]] and ![[board.png]].\n`[[code]]` [[gone]]\n```\n[[block]]\n```\n";
+    let replaced = replace_wiki_links(note, |target, alias, embed| match (target, embed) {
+        ("plan", _) => Some(format!("[{}](#plan)", alias.unwrap_or(target))),
+        (_, true) => None,
+        _ => Some(target.to_uppercase()),
+    });
+
+    assert_eq!(
+        "See [the plan](#plan) and ![[board.png]].\n`[[code]]` GONE\n```\n[[block]]\n```\n",
+        replaced
+    );
+    assert_eq!("weekly-review", heading_anchor("Weekly review!"));
+}
@@ -0,0 +1,396 @@
+use chrono::{Datelike, Local, NaiveDateTime};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+const DEFAULT_DATE_FORMAT: &str = "YYYY-MM-DD";
+const DEFAULT_TIME_FORMAT: &str = "HH:mm";
+
+/// `.obsidian/daily-notes.json`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct DailyNotes {
+    format: String,
+    folder: String,
+    template: String,
+}
+
+/// `.obsidian/templates.json`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Templates {
+    folder: String,
+    date_format: String,
+    time_format: String,
+}
+
+/// `.obsidian/app.json`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AppSettings {
+    attachment_folder_path: String,
+    use_markdown_links: bool,
+    new_file_location: String,
+    new_file_folder_path: String,
+}
+
+/**
+ * The settings of an Obsidian vault that change where notes are created
+ * and what they contain. Missing files and fields use Obsidian's defaults.
+ */
+#[derive(Debug)]
+pub struct Obsidian {
+    vault_path: PathBuf,
+    daily_notes: DailyNotes,
+    templates: Templates,
+    app: AppSettings,
+}
+
+impl Obsidian {
+    /// Reads the settings of the vault at `vault_path`, or returns `None` if
+    /// it isn't an Obsidian vault.
+    pub fn load(vault_path: &Path) -> JeffResult<Option<Self>> {
+        let settings = vault_path.join(".obsidian");
+        if !settings.is_dir() {
+            return Ok(None);
+        }
+
+        Ok(Some(Obsidian {
+            vault_path: vault_path.to_path_buf(),
+            daily_notes: read_settings(&settings.join("daily-notes.json"))?,
+            templates: read_settings(&settings.join("templates.json"))?,
+            app: read_settings(&settings.join("app.json"))?,
+        }))
+    }
+
+    /// Returns the path of the daily note for `time`. The date format may
+    /// contain slashes, which place the note in subfolders.
+    pub fn daily_note_path(&self, time: &NaiveDateTime) -> PathBuf {
+        let format = or_default(&self.daily_notes.format, DEFAULT_DATE_FORMAT);
+        let name = format_moment(time, format);
+
+        self.vault_path
+            .join(self.daily_notes.folder.trim_matches('/'))
+            .join(format!("{}.md", name))
+    }
+
+    /// Returns the contents of a new daily note, from the daily note
+    /// template if there is one.
+    pub fn daily_note_contents(&self, time: &NaiveDateTime, title: &str) -> JeffResult<String> {
+        if self.daily_notes.template.trim().is_empty() {
+            return Ok(String::new());
+        }
+
+        let template = self.note_path(&self.vault_path, &self.daily_notes.template);
+        let format = or_default(&self.daily_notes.format, DEFAULT_DATE_FORMAT);
+
+        Ok(render_template(
+            &read_to_string(template)?,
+            time,
+            title,
+            format,
+            DEFAULT_TIME_FORMAT,
+        ))
+    }
+
+    /// Returns the path of the template called `name` inside of the
+    /// template folder, if one is configured.
+    pub fn template_path(&self, name: &str) -> Option<PathBuf> {
+        if self.templates.folder.trim().is_empty() {
+            return None;
+        }
+
+        let folder = self
+            .vault_path
+            .join(self.templates.folder.trim_matches('/'));
+        Some(self.note_path(&folder, name))
+    }
+
+    /// Fills in the `{{title}}`, `{{date}}` and `{{time}}` variables of a
+    /// template, as Obsidian's templates plugin does.
+    pub fn render_template(&self, template: &str, title: &str) -> String {
+        render_template(
+            template,
+            &Local::now().naive_local(),
+            title,
+            or_default(&self.templates.date_format, DEFAULT_DATE_FORMAT),
+            or_default(&self.templates.time_format, DEFAULT_TIME_FORMAT),
+        )
+    }
+
+    /// Returns the folder that new notes are created in, or `None` to use
+    /// the active folder.
+    pub fn new_note_folder(&self) -> Option<PathBuf> {
+        match self.app.new_file_location.as_str() {
+            "root" => Some(self.vault_path.to_owned()),
+            "folder" => Some(
+                self.vault_path
+                    .join(self.app.new_file_folder_path.trim_matches('/')),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Whether links are written as markdown links rather than wiki links.
+    pub fn uses_markdown_links(&self) -> bool {
+        self.app.use_markdown_links
+    }
+
+    /// Returns the folder that attachments of the note in `note_folder` are
+    /// stored in.
+    pub fn attachment_folder(&self, note_folder: &Path) -> PathBuf {
+        let path = self.app.attachment_folder_path.as_str();
+        match path.strip_prefix("./") {
+            Some(relative) => note_folder.join(relative),
+            None if path == "." => note_folder.to_path_buf(),
+            None => self.vault_path.join(path.trim_matches('/')),
+        }
+    }
+
+    /// Resolves a note path, which may be missing its `.md` extension.
+    fn note_path(&self, folder: &Path, name: &str) -> PathBuf {
+        let name = name.trim_matches('/');
+        if name.ends_with(".md") {
+            folder.join(name)
+        } else {
+            folder.join(format!("{}.md", name))
+        }
+    }
+}
+
+fn read_settings<T: DeserializeOwned + Default>(path: &Path) -> JeffResult<T> {
+    if !path.is_file() {
+        return Ok(T::default());
+    }
+
+    serde_json::from_str(&read_to_string(path)?)
+        .map_err(|err| Error::CorruptedFile(path.to_path_buf(), err.to_string()))
+}
+
+fn or_default<'a>(value: &'a str, default: &'a str) -> &'a str {
+    if value.trim().is_empty() {
+        default
+    } else {
+        value
+    }
+}
+
+/// Replaces `{{title}}`, `{{date}}`, `{{time}}`, `{{date:FORMAT}}` and
+/// `{{time:FORMAT}}` in `template`.
+fn render_template(
+    template: &str,
+    time: &NaiveDateTime,
+    title: &str,
+    date_format: &str,
+    time_format: &str,
+) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end) else {
+            break;
+        };
+        let variable = rest[start + 2..end].trim();
+        let (name, format) = match variable.split_once(':') {
+            Some((name, format)) => (name.trim(), Some(format.trim())),
+            None => (variable, None),
+        };
+
+        let value = match (name.to_lowercase().as_str(), format) {
+            ("title", None) => title.to_owned(),
+            ("date", format) => format_moment(time, format.unwrap_or(date_format)),
+            ("time", format) => format_moment(time, format.unwrap_or(time_format)),
+            _ => rest[start..end + 2].to_owned(),
+        };
+
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&value);
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+
+    rendered
+}
+
+/// Formats a time with a Moment.js format string, like `YYYY-MM-DD`. Text
+/// in square brackets is kept as is.
+pub fn format_moment(time: &NaiveDateTime, format: &str) -> String {
+    // longest tokens first, so that `MM` isn't read as `M` twice
+    const TOKENS: [(&str, &str); 30] = [
+        ("YYYY", "%Y"),
+        ("YY", "%y"),
+        ("MMMM", "%B"),
+        ("MMM", "%b"),
+        ("MM", "%m"),
+        ("M", "%-m"),
+        ("DDDD", "%j"),
+        ("DDD", "%-j"),
+        ("DD", "%d"),
+        ("Do", ""),
+        ("D", "%-d"),
+        ("dddd", "%A"),
+        ("ddd", "%a"),
+        ("d", "%w"),
+        ("GGGG", "%G"),
+        ("gggg", "%G"),
+        ("WW", "%V"),
+        ("ww", "%V"),
+        ("W", "%-V"),
+        ("w", "%-V"),
+        ("HH", "%H"),
+        ("H", "%-H"),
+        ("hh", "%I"),
+        ("h", "%-I"),
+        ("mm", "%M"),
+        ("m", "%-M"),
+        ("ss", "%S"),
+        ("s", "%-S"),
+        ("A", "%p"),
+        ("a", "%P"),
+    ];
+
+    let mut formatted = String::new();
+    let mut rest = format;
+
+    'outer: while let Some(next) = rest.chars().next() {
+        if next == '[' {
+            if let Some(end) = rest.find(']') {
+                formatted.push_str(&rest[1..end]);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+
+        if let Some(remaining) = rest.strip_prefix('Q') {
+            formatted.push_str(&((time.month() - 1) / 3 + 1).to_string());
+            rest = remaining;
+            continue;
+        }
+
+        for (token, chrono_format) in TOKENS {
+            if let Some(remaining) = rest.strip_prefix(token) {
+                if token == "Do" {
+                    formatted.push_str(&ordinal(time.day()));
+                } else {
+                    formatted.push_str(&time.format(chrono_format).to_string());
+                }
+                rest = remaining;
+                continue 'outer;
+            }
+        }
+
+        formatted.push(next);
+        rest = &rest[next.len_utf8()..];
+    }
+
+    formatted
+}
+
+/// e.g. `1st`, `22nd`, `13th`
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{}{}", day, suffix)
+}
+
+#[test]
+fn format_moment_tests() {
+    let time = chrono::NaiveDate::from_ymd_opt(2024, 3, 2)
+        .unwrap()
+        .and_hms_opt(9, 5, 0)
+        .unwrap();
+
+    assert_eq!("2024-03-02", format_moment(&time, "YYYY-MM-DD"));
+    assert_eq!(
+        "2024/03/Saturday 2nd",
+        format_moment(&time, "YYYY/MM/dddd Do")
+    );
+    assert_eq!("Q1 [week] 09", format_moment(&time, "[Q]Q [[week]] WW"));
+    assert_eq!("09:05 am", format_moment(&time, "HH:mm a"));
+    assert_eq!("2 Mar 24", format_moment(&time, "D MMM YY"));
+    assert_eq!("11th 21st 13th", [11, 21, 13].map(ordinal).join(" "));
+}
+
+#[test]
+fn obsidian_settings_tests() {
+    let vault = std::env::temp_dir().join(format!("jeff_obsidian_{}", std::process::id()));
+    let settings = vault.join(".obsidian");
+    std::fs::create_dir_all(settings.join("plugins")).unwrap();
+    let time = chrono::NaiveDate::from_ymd_opt(2024, 3, 2)
+        .unwrap()
+        .and_hms_opt(9, 5, 0)
+        .unwrap();
+
+    // defaults
+    let obsidian = Obsidian::load(&vault).unwrap().unwrap();
+    assert_eq!(vault.join("2024-03-02.md"), obsidian.daily_note_path(&time));
+    assert_eq!("", obsidian.daily_note_contents(&time, "x").unwrap());
+    assert!(obsidian.template_path("daily").is_none());
+    assert!(obsidian.new_note_folder().is_none());
+    assert_eq!(vault, obsidian.attachment_folder(&vault.join("folder")));
+
+    std::fs::create_dir_all(vault.join("Templates")).unwrap();
+    std::fs::write(
+        vault.join("Templates/Daily.md"),
+        "# {{title}}\n{{date:dddd}}",
+    )
+    .unwrap();
+    std::fs::write(
+        settings.join("daily-notes.json"),
+        r#"{"format": "YYYY/MM/DD", "folder": "Journal/", "template": "Templates/Daily"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        settings.join("templates.json"),
+        r#"{"folder": "Templates"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        settings.join("app.json"),
+        r#"{"attachmentFolderPath": "./assets", "newFileLocation": "folder", "newFileFolderPath": "Inbox"}"#,
+    )
+    .unwrap();
+
+    let obsidian = Obsidian::load(&vault).unwrap().unwrap();
+    assert_eq!(
+        vault.join("Journal/2024/03/02.md"),
+        obsidian.daily_note_path(&time)
+    );
+    assert_eq!(
+        "# 02\nSaturday",
+        obsidian.daily_note_contents(&time, "02").unwrap()
+    );
+    assert_eq!(
+        Some(vault.join("Templates/Daily.md")),
+        obsidian.template_path("Daily")
+    );
+    assert_eq!(Some(vault.join("Inbox")), obsidian.new_note_folder());
+    assert_eq!(
+        vault.join("folder/assets"),
+        obsidian.attachment_folder(&vault.join("folder"))
+    );
+    assert_eq!(
+        "{{unknown}} a",
+        obsidian.render_template("{{unknown}} {{title}}", "a")
+    );
+
+    std::fs::write(settings.join("app.json"), "{").unwrap();
+    assert!(Obsidian::load(&vault).is_err());
+    assert!(
+        Obsidian::load(&std::env::temp_dir().join("jeff_not_a_vault"))
+            .unwrap()
+            .is_none()
+    );
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
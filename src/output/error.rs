@@ -1,18 +1,25 @@
-use std::fmt::Display;
+use std::{fmt::Display, path::PathBuf};
 
 use colored::*;
 
 use crate::enums::Item;
+use crate::items::NOTE_EXTENSIONS;
 
-pub type JeffResult<T> = anyhow::Result<T>;
+pub type JeffResult<T> = Result<T, Error>;
 
 #[allow(unused)]
 #[derive(Debug)]
 pub enum Error {
     InternalError, // internal errors: unwrap calls that fail, internal err result matches
-    FileError(String, std::io::Error), // errors concering FileIO trait
+    FileError(String, std::io::Error), // errors concering FileIO trait, with the file's path
+    CorruptedFile(PathBuf, String),
+    UnsupportedSchema(PathBuf, u32, u32),
     AliasDoesNotExist(String),
     InvalidName,
+    UnrecognisedExtension(String),
+    /// an extension that jeff can't use for notes in any vault
+    UnsupportedNoteExtension(String),
+    AmbiguousName(String, Vec<String>),
     SameName,
     SameLocation,
     PathNotFound,
@@ -20,12 +27,98 @@ pub enum Error {
     ItemNotFound(Item, String),
     VaultAlreadyExists(String),
     VaultNotFound(String),
+    VaultPathMissing(String, PathBuf),
     NotInsideVault,
     AlreadyInVault(String),
+    NestedVault(String),
     OutOfBounds,
     EditorNotFound,
+    SectionNotFound(String),
+    TrashItemNotFound(String),
+    RevisionNotFound(String),
+    InvalidInput(String),
+    AlreadyInShell,
+    NothingToUndo,
+    GitFailed(String),
+    ChangedSinceOperation(String),
+    InputRequired(String),
+    MissingValue(String),
+    TemplateNameRequired,
+    TemplateNotFound(String),
+    InvalidPath(PathBuf),
+    OutputInsideVault(PathBuf),
+    UnsupportedArchive(PathBuf),
+    /// a `--format` that the command can't print
+    UnsupportedFormat(String),
+    InvalidArchive(PathBuf),
+    EmptyText,
     MoveError(String), // this will be removed upon switching to custom recursive move fn
-    Undefined(std::io::Error),
+    Io(std::io::Error),
+}
+
+/// The class of an [Error]. Each class exits with its own code so that
+/// scripts can tell failures apart without parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    General,
+    NotFound,
+    AlreadyExists,
+    OutOfBounds,
+    Io,
+    Config,
+}
+
+impl ErrorKind {
+    /// Exit code for the class. `2` is left to clap for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::General => 1,
+            ErrorKind::NotFound => 3,
+            ErrorKind::AlreadyExists => 4,
+            ErrorKind::OutOfBounds => 5,
+            ErrorKind::Io => 6,
+            ErrorKind::Config => 7,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::General => "general",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::AlreadyExists => "already_exists",
+            ErrorKind::OutOfBounds => "out_of_bounds",
+            ErrorKind::Io => "io",
+            ErrorKind::Config => "config",
+        }
+    }
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::PathNotFound
+            | Error::AliasDoesNotExist(_)
+            | Error::ItemNotFound(..)
+            | Error::VaultNotFound(_)
+            | Error::VaultPathMissing(..)
+            | Error::SectionNotFound(_)
+            | Error::TrashItemNotFound(_)
+            | Error::RevisionNotFound(_)
+            | Error::TemplateNotFound(_)
+            | Error::EditorNotFound => ErrorKind::NotFound,
+            Error::ItemAlreadyExists(..) | Error::VaultAlreadyExists(_) => ErrorKind::AlreadyExists,
+            Error::OutOfBounds => ErrorKind::OutOfBounds,
+            Error::FileError(..) | Error::Io(_) => ErrorKind::Io,
+            Error::MissingValue(_) | Error::CorruptedFile(..) | Error::UnsupportedSchema(..) => {
+                ErrorKind::Config
+            }
+            _ => ErrorKind::General,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
 }
 
 impl Display for Error {
@@ -34,14 +127,36 @@ impl Display for Error {
             f,
             "{}",
             match self {
-                Error::FileError(title, error) => format!(
-                    "{} file error: {}",
-                    title,
+                Error::FileError(path, error) => format!(
+                    "couldn't access {}: {}",
+                    path,
                     process_io_error(error.to_string())
                 ),
+                Error::CorruptedFile(path, error) =>
+                    format!("couldn't parse {}: {}", path.display(), error),
+                Error::UnsupportedSchema(path, version, supported) => format!(
+                    "{} has schema version {}, but this version of jeff only supports up to {}",
+                    path.display(),
+                    version,
+                    supported
+                ),
                 Error::InvalidName => "invalid name".to_string(),
+                Error::UnrecognisedExtension(extension) => format!(
+                    "notes ending in .{} aren't recognised in this vault (see jf config note.extensions)",
+                    extension
+                ),
+                Error::UnsupportedNoteExtension(extension) => format!(
+                    "notes can't end in .{}, note extensions are picked from .{}",
+                    extension,
+                    NOTE_EXTENSIONS.join(", .")
+                ),
+                Error::AmbiguousName(name, full_names) => format!(
+                    "more than one note is named {}, use one of {}",
+                    name.blue(),
+                    full_names.join(", ")
+                ),
                 Error::AliasDoesNotExist(name) =>
-                    format!("alias for note \x1b[0;34m{}\x1b[0m does not exist", name),
+                    format!("alias for note {} does not exist", name.blue()),
                 Error::SameName => "new name is same as old name".to_string(),
                 Error::SameLocation => "new location is same as old location".to_string(),
                 Error::PathNotFound => "couldn't find the path specified".to_string(),
@@ -54,18 +169,72 @@ impl Display for Error {
                     format!("{} {} not found", item_type.fs_name(), name.blue()),
                 Error::VaultAlreadyExists(name) => format!("vault {} already exists", name),
                 Error::VaultNotFound(name) => format!("vault {} doesn't exist", name),
+                Error::VaultPathMissing(name, path) => format!(
+                    "vault {} isn't at {} anymore (use jf vault relink if it moved)",
+                    name.blue(),
+                    path.display()
+                ),
                 Error::NotInsideVault => "not inside a vault".to_string(),
                 Error::AlreadyInVault(name) => format!("already in vault {}", name),
                 Error::OutOfBounds => "path crosses the bounds of vault".to_string(),
                 Error::EditorNotFound => "editor not found".to_string(),
+                Error::InvalidInput(input) => format!("couldn't parse {}", input),
+                Error::InputRequired(prompt) =>
+                    format!("{} (input required, use --yes to confirm)", prompt),
+                Error::MissingValue(name) => format!("a value is required for {}", name),
+                Error::AlreadyInShell => "already in the shell".to_string(),
+                Error::NothingToUndo => "nothing to undo".to_string(),
+                Error::NestedVault(name) => format!(
+                    "vaults can't be inside of each other (vault {})",
+                    name.blue()
+                ),
+                Error::GitFailed(error) => format!("git failed: {}", error),
+                Error::RevisionNotFound(id) => format!("revision {} not found", id.blue()),
+                Error::ChangedSinceOperation(operation) =>
+                    format!("can't undo {}, the files have changed since", operation),
+                Error::TrashItemNotFound(name) => format!("{} not found in the trash", name.blue()),
+                Error::SectionNotFound(heading) => format!("section {} not found", heading.blue()),
+                Error::TemplateNameRequired => "a template name is required".to_string(),
+                Error::TemplateNotFound(name) => format!("template {} not found", name.blue()),
+                Error::InvalidPath(path) => format!("invalid path {}", path.display()),
+                Error::OutputInsideVault(path) =>
+                    format!("can't write to {}, it is inside of the vault", path.display()),
+                Error::UnsupportedFormat(format) =>
+                    format!("--format {} only works with export bundle", format),
+                Error::UnsupportedArchive(path) => format!(
+                    "unsupported archive {}, use a .tar.gz or .zip file",
+                    path.display()
+                ),
+                Error::InvalidArchive(path) => format!(
+                    "{} isn't a vault archive, everything in it has to be inside of one folder",
+                    path.display()
+                ),
+                Error::EmptyText => "no text given".to_string(),
                 Error::MoveError(msg) => msg.to_owned(),
-                Error::Undefined(error) => format!("undefined error: {}", error),
-                _ => "error msg not set".to_string(),
+                Error::Io(error) => process_io_error(error.to_string()),
+                Error::InternalError => "internal error".to_string(),
             }
         )
     }
 }
 
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<rustyline::error::ReadlineError> for Error {
+    fn from(error: rustyline::error::ReadlineError) -> Self {
+        match error {
+            rustyline::error::ReadlineError::Io(error) => Error::Io(error),
+            error => Error::Io(std::io::Error::other(error)),
+        }
+    }
+}
+
 impl From<Error> for String {
     fn from(error: Error) -> Self {
         format!("{}", error).to_string()
@@ -79,3 +248,17 @@ fn process_io_error(error: String) -> String {
     }
     error
 }
+
+#[test]
+fn exit_code_tests() {
+    assert_eq!(3, Error::VaultNotFound("a".to_string()).exit_code());
+    assert_eq!(
+        3,
+        Error::ItemNotFound(Item::Nt, "a".to_string()).exit_code()
+    );
+    assert_eq!(4, Error::VaultAlreadyExists("a".to_string()).exit_code());
+    assert_eq!(5, Error::OutOfBounds.exit_code());
+    assert_eq!(6, Error::from(std::io::Error::other("disk")).exit_code());
+    assert_eq!(7, Error::MissingValue("editor".to_string()).exit_code());
+    assert_eq!(1, Error::InvalidName.exit_code());
+}
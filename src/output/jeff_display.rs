@@ -9,20 +9,6 @@ pub trait Colored {
     }
 }
 
-pub trait JeffDisplay {
-    fn to_display_string(&self) -> String;
-}
-
-impl<T> JeffDisplay for T
-where
-    T: Item + Colored,
-{
-    fn to_display_string(&self) -> String {
-        let color = <Self as Colored>::get_color();
-        self.get_name().color(color).to_string()
-    }
-}
-
 impl Colored for Note {
     fn get_color() -> Color {
         CONFIG.lock().unwrap().get_note_color()
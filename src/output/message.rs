@@ -1,21 +1,70 @@
+use crate::attachments::MissingLink;
+use crate::doctor::Problem;
 use crate::enums::{ConfigType, Item, VaultItem};
+use crate::export::Bundle;
+use crate::items::{Folder, Note, TreeNode, Vault};
+use crate::output::Colored;
+use crate::revisions::{DiffLine, Revision};
+use crate::state::{Config, HistoryEntry, TrashItem, VaultInfo};
 use colored::Colorize;
+use serde_json::{json, Value};
 use std::fmt::Display;
+use std::path::PathBuf;
 
 pub enum Message {
     VaultEntered(String),
+    VaultImported(String),
+    VaultRelinked(String, PathBuf),
+    /// a vault whose folder was gone was forgotten
+    VaultUnregistered(String),
+    /// name of the vault, and path of the archive
+    VaultArchived(String, PathBuf),
+    /// name of the vault, and the path it was extracted to
+    VaultUnarchived(String, PathBuf),
     #[allow(unused)]
     NoteAliasCreated(String, String),
     #[allow(unused)]
     NoteAliasRemoved(String, String),
     TemplateCreated(String),
+    NoteAppended(String),
+    NotePrepended(String),
     ItemCreated(Item, String),
     ItemRemoved(Item, String),
+    ItemRestored(Item, String),
     ItemRenamed(Item, String, String),
     ItemMoved(Item, String),
     ItemVMoved(VaultItem, String, String),
     FolderChanged,
     Config(ConfigType, String),
+    ShowConfig(Config),
+    /// name of the vault, name of the active folder, and the items inside of it
+    List(String, Option<String>, Vec<TreeNode>),
+    Templates(Vec<TreeNode>),
+    /// vaults, and whether to show their locations
+    Vaults(Vec<VaultInfo>, bool),
+    Trash(Vec<TrashItem>),
+    /// number of items deleted
+    TrashEmptied(usize),
+    /// descriptions of the undone operations
+    Undone(Vec<String>),
+    History(Vec<HistoryEntry>),
+    /// name of the note, and its revisions
+    Revisions(String, Vec<Revision>),
+    /// name of the note, the revision it is compared with, and the difference
+    Diff(String, String, Vec<DiffLine>),
+    /// name of the note, and the revision it was restored to
+    NoteRestored(String, String),
+    /// name of the note, path of the attachment in the vault, and whether
+    /// it was copied (rather than already being in the vault)
+    Attached(String, PathBuf, bool),
+    /// paths of attachments in the vault
+    Attachments(Vec<PathBuf>),
+    MissingEmbeds(Vec<MissingLink>),
+    Doctor(Vec<Problem>),
+    /// output folder, and number of exported notes
+    Exported(PathBuf, usize),
+    Bundle(Bundle),
+    #[allow(unused)]
     Custom(String),
     Empty,
 }
@@ -24,57 +73,492 @@ impl Message {
     fn create_message(content: String) -> String {
         format!("ϟ {} ϟ {}", "Jeff".yellow(), content)
     }
+
+    /// Adds the changed lines of a diff, with a few lines of context around
+    /// each change.
+    fn diff_lines(lines: &mut Vec<String>, diff: &[DiffLine]) {
+        const CONTEXT: usize = 3;
+
+        let changed: Vec<usize> = (0..diff.len())
+            .filter(|&index| !matches!(diff[index], DiffLine::Same(_)))
+            .collect();
+        let is_shown = |index: usize| {
+            changed
+                .iter()
+                .any(|&change| index + CONTEXT >= change && index <= change + CONTEXT)
+        };
+
+        let mut skipped = false;
+        for (index, line) in diff.iter().enumerate() {
+            if !is_shown(index) {
+                skipped = true;
+                continue;
+            }
+            if skipped && lines.len() > 1 {
+                lines.push("...".dimmed().to_string());
+            }
+            skipped = false;
+
+            lines.push(match line {
+                DiffLine::Same(text) => format!("  {}", text),
+                DiffLine::Added(text) => format!("+ {}", text).green().to_string(),
+                DiffLine::Removed(text) => format!("- {}", text).red().to_string(),
+            });
+        }
+    }
+
+    fn tree_lines(lines: &mut Vec<String>, nodes: &[TreeNode], buffer: Option<String>) {
+        let (folder_color, note_color) = (Folder::get_color(), Note::get_color());
+        let (folder_prefix, note_prefix) = match &buffer {
+            Some(buffer) => (format!("{} ", buffer), format!("{}    ", buffer)),
+            None => (String::new(), String::new()),
+        };
+
+        for folder in nodes.iter().filter(|node| node.item_type == Item::Fd) {
+            lines.push(format!(
+                "{}└── {}",
+                folder_prefix,
+                folder.name.color(folder_color)
+            ));
+            let buffer = buffer
+                .as_ref()
+                .map_or(String::new(), |buffer| format!("{}    ", buffer));
+            Message::tree_lines(lines, &folder.children, Some(buffer));
+        }
+
+        let notes: Vec<&TreeNode> = nodes
+            .iter()
+            .filter(|node| node.item_type == Item::Nt)
+            .collect();
+        for (i, note) in notes.iter().enumerate() {
+            let branch = if i == notes.len() - 1 {
+                "└──"
+            } else {
+                "├──"
+            };
+            lines.push(format!(
+                "{}{} {}",
+                note_prefix,
+                branch,
+                note.name.color(note_color)
+            ));
+        }
+    }
+
+    fn tree_to_json(nodes: &[TreeNode]) -> Value {
+        nodes
+            .iter()
+            .map(|node| match node.item_type {
+                Item::Fd => json!({
+                    "name": node.name,
+                    "type": "folder",
+                    "children": Message::tree_to_json(&node.children),
+                }),
+                _ => json!({ "name": node.name, "type": "note" }),
+            })
+            .collect()
+    }
+
+    /// Returns the message as json. Every message is an object with a "type".
+    pub fn to_json(&self) -> Value {
+        match self {
+            Message::VaultEntered(name) => json!({ "type": "vault_entered", "vault": name }),
+            Message::VaultImported(name) => json!({ "type": "vault_imported", "vault": name }),
+            Message::VaultRelinked(name, path) => {
+                json!({ "type": "vault_relinked", "vault": name, "path": path })
+            }
+            Message::VaultUnregistered(name) => {
+                json!({ "type": "vault_unregistered", "vault": name })
+            }
+            Message::VaultArchived(name, archive) => {
+                json!({ "type": "vault_archived", "vault": name, "archive": archive })
+            }
+            Message::VaultUnarchived(name, path) => {
+                json!({ "type": "vault_unarchived", "vault": name, "path": path })
+            }
+            Message::NoteAliasCreated(note, alias) => {
+                json!({ "type": "note_alias_created", "note": note, "alias": alias })
+            }
+            Message::NoteAliasRemoved(note, alias) => {
+                json!({ "type": "note_alias_removed", "note": note, "alias": alias })
+            }
+            Message::TemplateCreated(name) => {
+                json!({ "type": "template_created", "template": name })
+            }
+            Message::NoteAppended(name) => json!({ "type": "note_appended", "note": name }),
+            Message::NotePrepended(name) => json!({ "type": "note_prepended", "note": name }),
+            Message::ItemCreated(item_type, name) => {
+                json!({ "type": "item_created", "item_type": item_type.full(), "name": name })
+            }
+            Message::ItemRemoved(item_type, name) => {
+                json!({ "type": "item_removed", "item_type": item_type.full(), "name": name })
+            }
+            Message::ItemRestored(item_type, name) => {
+                json!({ "type": "item_restored", "item_type": item_type.full(), "name": name })
+            }
+            Message::ItemRenamed(item_type, name, new_name) => json!({
+                "type": "item_renamed",
+                "item_type": item_type.full(),
+                "name": name,
+                "new_name": new_name,
+            }),
+            Message::ItemMoved(item_type, name) => {
+                json!({ "type": "item_moved", "item_type": item_type.full(), "name": name })
+            }
+            Message::ItemVMoved(item_type, name, vault) => json!({
+                "type": "item_moved_to_vault",
+                "item_type": item_type.full(),
+                "name": name,
+                "vault": vault,
+            }),
+            Message::FolderChanged => json!({ "type": "folder_changed" }),
+            Message::Config(config_type, value) => {
+                json!({ "type": "config", "key": config_type.to_str(), "value": value })
+            }
+            Message::ShowConfig(config) => json!({ "type": "show_config", "config": config }),
+            Message::List(vault, folder, items) => json!({
+                "type": "list",
+                "vault": vault,
+                "folder": folder,
+                "items": Message::tree_to_json(items),
+            }),
+            Message::Templates(items) => {
+                json!({ "type": "templates", "items": Message::tree_to_json(items) })
+            }
+            Message::Vaults(vaults, _) => json!({
+                "type": "vaults",
+                "vaults": vaults
+                    .iter()
+                    .map(|vault| json!({
+                        "name": vault.name,
+                        "location": vault.path,
+                        "current": vault.current,
+                    }))
+                    .collect::<Vec<Value>>(),
+            }),
+            Message::Trash(items) => json!({
+                "type": "trash",
+                "items": items
+                    .iter()
+                    .map(|item| json!({
+                        "id": item.id,
+                        "name": item.name(),
+                        "item_type": item.item_type.full(),
+                        "original_path": item.original_path,
+                        "removed_at": item.removed_at.to_rfc3339(),
+                    }))
+                    .collect::<Vec<Value>>(),
+            }),
+            Message::TrashEmptied(count) => json!({ "type": "trash_emptied", "count": count }),
+            Message::Undone(operations) => json!({ "type": "undone", "operations": operations }),
+            Message::History(entries) => json!({ "type": "history", "entries": entries }),
+            Message::Revisions(note, revisions) => json!({
+                "type": "revisions",
+                "note": note,
+                "revisions": revisions
+                    .iter()
+                    .map(|revision| json!({
+                        "id": revision.id,
+                        "time": revision.time.to_rfc3339(),
+                        "summary": revision.summary,
+                    }))
+                    .collect::<Vec<Value>>(),
+            }),
+            Message::Diff(note, revision, lines) => {
+                json!({ "type": "diff", "note": note, "revision": revision, "lines": lines })
+            }
+            Message::NoteRestored(note, revision) => {
+                json!({ "type": "note_restored", "note": note, "revision": revision })
+            }
+            Message::Attached(note, attachment, copied) => json!({
+                "type": "attached",
+                "note": note,
+                "attachment": attachment,
+                "copied": copied,
+            }),
+            Message::Attachments(attachments) => {
+                json!({ "type": "attachments", "attachments": attachments })
+            }
+            Message::MissingEmbeds(embeds) => json!({
+                "type": "missing_embeds",
+                "embeds": embeds
+                    .iter()
+                    .map(|embed| json!({ "note": embed.note, "target": embed.target }))
+                    .collect::<Vec<Value>>(),
+            }),
+            Message::Doctor(problems) => json!({
+                "type": "doctor",
+                "problems": problems
+                    .iter()
+                    .map(|problem| json!({
+                        "check": problem.check.name(),
+                        "subject": problem.subject,
+                        "detail": problem.detail,
+                        "fixed": problem.fixed,
+                    }))
+                    .collect::<Vec<Value>>(),
+            }),
+            Message::Exported(path, notes) => {
+                json!({ "type": "exported", "path": path, "notes": notes })
+            }
+            Message::Bundle(bundle) => json!({ "type": "bundle", "notes": bundle.to_json() }),
+            Message::Custom(content) => json!({ "type": "custom", "message": content }),
+            Message::Empty => json!({ "type": "empty" }),
+        }
+    }
+
+    /// Returns listings as plain lines, or `None` for regular messages.
+    fn listing(&self) -> Option<String> {
+        let mut lines = vec![];
+
+        match self {
+            Message::List(vault, folder, items) => {
+                let (vault_color, folder_color) = (Vault::get_color(), Folder::get_color());
+                lines.push(match folder {
+                    Some(folder) => format!(
+                        "{} > {}",
+                        vault.color(vault_color),
+                        folder.color(folder_color)
+                    ),
+                    None => vault.color(vault_color).to_string(),
+                });
+                Message::tree_lines(&mut lines, items, None);
+            }
+            Message::Templates(items) => Message::tree_lines(&mut lines, items, None),
+            Message::Vaults(vaults, show_loc) => {
+                for vault in vaults {
+                    let mut line = if vault.current {
+                        format!("👉 {}", vault.name.blue())
+                    } else {
+                        format!("   {}", vault.name)
+                    };
+                    if *show_loc {
+                        line.push_str(&format!(" \t {}", vault.path.display()));
+                    }
+                    lines.push(line);
+                }
+            }
+            Message::Trash(items) => {
+                for item in items {
+                    let color = match item.item_type {
+                        Item::Vault | Item::Vl => Vault::get_color(),
+                        Item::Folder | Item::Fd => Folder::get_color(),
+                        Item::Note | Item::Nt => Note::get_color(),
+                    };
+                    lines.push(format!(
+                        "{}  {:<6}  {}",
+                        item.removed_at.format("%Y-%m-%d %H:%M"),
+                        item.item_type.full(),
+                        item.original_path.display().to_string().color(color)
+                    ));
+                }
+            }
+            Message::History(entries) => {
+                for entry in entries {
+                    lines.push(format!(
+                        "{}  {}  {}",
+                        entry.time.format("%Y-%m-%d %H:%M"),
+                        entry
+                            .vault
+                            .as_deref()
+                            .unwrap_or("-")
+                            .color(Vault::get_color()),
+                        entry.command
+                    ));
+                    for path in &entry.paths {
+                        lines.push(format!("    {}", path.display()));
+                    }
+                }
+            }
+            Message::Revisions(_, revisions) => {
+                for revision in revisions {
+                    lines.push(format!(
+                        "{}  {}  {}",
+                        revision.id.yellow(),
+                        revision.time.format("%Y-%m-%d %H:%M"),
+                        revision.summary
+                    ));
+                }
+            }
+            Message::Diff(note, revision, diff) => {
+                lines.push(format!(
+                    "{} @ {}",
+                    note.color(Note::get_color()),
+                    revision.yellow()
+                ));
+                Message::diff_lines(&mut lines, diff);
+            }
+            Message::Attachments(attachments) => {
+                for attachment in attachments {
+                    lines.push(attachment.display().to_string());
+                }
+            }
+            Message::MissingEmbeds(embeds) => {
+                for embed in embeds {
+                    lines.push(format!(
+                        "{}  {}",
+                        embed.note.display().to_string().color(Note::get_color()),
+                        embed.target
+                    ));
+                }
+            }
+            Message::Bundle(bundle) => lines.push(bundle.to_markdown()),
+            Message::Doctor(problems) => {
+                if problems.is_empty() {
+                    lines.push("no problems found".to_string());
+                }
+                for problem in problems {
+                    let mut line =
+                        format!("{:<16}{}", problem.check.description(), problem.subject);
+                    if let Some(detail) = &problem.detail {
+                        line.push_str(&format!("  {}", detail.blue()));
+                    }
+                    if problem.fixed {
+                        line.push_str(&format!("  {}", "fixed".green()));
+                    }
+                    lines.push(line);
+                }
+            }
+            _ => return None,
+        }
+
+        Some(lines.join("\n"))
+    }
 }
 
 impl Display for Message {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(listing) = self.listing() {
+            return write!(f, "{}", listing);
+        }
+
         write!(
             f,
             "{}",
             Message::create_message(match self {
-                Message::VaultEntered(name) => format!("entered \x1b[0;34m{}\x1b[0m", name),
+                Message::VaultEntered(name) => format!("entered {}", name.blue()),
+                Message::VaultImported(name) => format!("vault {} imported", name.blue()),
+                Message::VaultArchived(name, archive) =>
+                    format!("vault {} archived to {}", name.blue(), archive.display()),
+                Message::VaultUnarchived(name, path) =>
+                    format!("vault {} restored to {}", name.blue(), path.display()),
+                Message::VaultRelinked(name, path) =>
+                    format!("vault {} relinked to {}", name.blue(), path.display()),
+                Message::VaultUnregistered(name) => {
+                    format!("vault {} unregistered, its folder was gone", name.blue())
+                }
                 Message::ItemCreated(item_type, name) =>
-                    format!("{} \x1b[0;34m{}\x1b[0m created", item_type.full(), name),
+                    format!("{} {} created", item_type.full(), name.blue()),
                 Message::ItemRemoved(item_type, name) =>
-                    format!("{} \x1b[0;34m{}\x1b[0m removed", item_type.full(), name),
+                    format!("{} {} moved to the trash", item_type.full(), name.blue()),
+                Message::ItemRestored(item_type, name) =>
+                    format!("{} {} restored", item_type.full(), name.blue()),
+                Message::TrashEmptied(count) => format!("deleted {} items from the trash", count),
+                Message::Undone(operations) => format!("undid {}", operations.join(", ")),
+                Message::NoteRestored(note, revision) =>
+                    format!("note {} restored to {}", note.blue(), revision.yellow()),
+                Message::Exported(path, notes) => format!(
+                    "exported {} note{} to {}",
+                    notes,
+                    if *notes == 1 { "" } else { "s" },
+                    path.display()
+                ),
+                Message::Attached(note, attachment, copied) => format!(
+                    "{} {} in note {}",
+                    if *copied {
+                        "attached"
+                    } else {
+                        "embedded existing attachment"
+                    },
+                    attachment.display(),
+                    note.blue()
+                ),
                 Message::ItemRenamed(item_type, name, new_name) => format!(
-                    "{} \x1b[0;34m{}\x1b[0m renamed to \x1b[0;34m{}\x1b[0m",
+                    "{} {} renamed to {}",
                     item_type.full(),
-                    name,
-                    new_name
+                    name.blue(),
+                    new_name.blue()
                 ),
                 Message::ItemMoved(item_type, name) =>
-                    format!("{} \x1b[0;34m{}\x1b[0m moved", item_type.full(), name),
+                    format!("{} {} moved", item_type.full(), name.blue()),
                 Message::ItemVMoved(item_type, name, vault_name) => format!(
-                    "{} \x1b[0;34m{}\x1b[0m moved to vault \x1b[0;34m{}\x1b[0m",
+                    "{} {} moved to vault {}",
                     item_type.full(),
-                    name,
-                    vault_name
+                    name.blue(),
+                    vault_name.blue()
                 ),
+                Message::NoteAppended(name) => format!("appended to note {}", name.blue()),
+                Message::NotePrepended(name) => format!("prepended to note {}", name.blue()),
                 Message::FolderChanged => "changed folder".to_string(),
                 Message::Config(config_type, value) => format!(
-                    "Configuration option [\x1b[0;34m{}\x1b[0m] is set to {}",
-                    config_type.to_str(),
+                    "Configuration option [{}] is set to {}",
+                    config_type.to_str().blue(),
                     value
                 ),
                 Message::NoteAliasCreated(note_name, alias_name) => {
                     format!(
-                        "created alias \x1b[0;34m{}\x1b[0m -> \x1b[0;34m{}\x1b[0m",
-                        note_name, alias_name
+                        "created alias {} -> {}",
+                        note_name.blue(),
+                        alias_name.blue()
                     )
                 }
                 Message::NoteAliasRemoved(note_name, alias_name) => {
                     format!(
-                        "removed alias \x1b[0;34m{}\x1b[0m -> \x1b[0;34m{}\x1b[0m",
-                        note_name, alias_name
+                        "removed alias {} -> {}",
+                        note_name.blue(),
+                        alias_name.blue()
                     )
                 }
                 Message::TemplateCreated(template_name) => {
                     format!("Created template [{}]", template_name.blue())
                 }
+                Message::ShowConfig(config) => format!("\nConfiguration\n---\n{}", config),
                 Message::Custom(content) => content.to_string(),
+                Message::List(..)
+                | Message::Templates(_)
+                | Message::Vaults(..)
+                | Message::Trash(_)
+                | Message::History(_)
+                | Message::Revisions(..)
+                | Message::Diff(..)
+                | Message::Attachments(_)
+                | Message::MissingEmbeds(_)
+                | Message::Doctor(_)
+                | Message::Bundle(_) => unreachable!(),
                 Message::Empty => "".to_string(),
             })
         )
     }
 }
+
+#[test]
+fn json_tests() {
+    let created = Message::ItemCreated(Item::Nt, "note".to_string());
+    assert_eq!(
+        json!({ "type": "item_created", "item_type": "note", "name": "note" }),
+        created.to_json()
+    );
+
+    let tree = vec![TreeNode {
+        name: "folder".to_string(),
+        item_type: Item::Fd,
+        children: vec![TreeNode {
+            name: "note".to_string(),
+            item_type: Item::Nt,
+            children: vec![],
+        }],
+    }];
+    assert_eq!(
+        json!({
+            "type": "list",
+            "vault": "vault",
+            "folder": null,
+            "items": [{
+                "name": "folder",
+                "type": "folder",
+                "children": [{ "name": "note", "type": "note" }],
+            }],
+        }),
+        Message::List("vault".to_string(), None, tree).to_json()
+    );
+}
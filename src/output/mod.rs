@@ -4,14 +4,19 @@ pub mod message;
 
 use std::fmt::Display;
 
+use colored::Colorize;
+use serde_json::json;
+
+use crate::enums::Format;
+
 pub use error::{Error, JeffResult};
 pub use jeff_display::*;
 pub use message::Message;
 
 pub enum Output {
     Message(Message),
-    #[allow(unused)]
     Error(Error),
+    Warning(String),
 }
 
 impl Display for Output {
@@ -21,8 +26,55 @@ impl Display for Output {
             "{}",
             match self {
                 Output::Message(msg) => msg.to_string(),
-                Output::Error(err) => format!("\x1b[0;31merror\x1b[0m: {}", err),
+                Output::Error(err) => format!("{}: {}", "error".red(), err),
+                Output::Warning(warning) => format!("{}: {}", "warning".yellow(), warning),
             }
         )
     }
 }
+
+/// Prints the outcome of a command.
+///
+/// In text format errors are printed to stderr. In json format every command
+/// prints a single object to stdout: `{"ok": true, "result": <message>}` on
+/// success and `{"ok": false, "error": {"kind": <class>, "code": <exit code>,
+/// "message": <text>}}` on failure.
+///
+/// Returns the exit code of the command.
+pub fn display_result(result: JeffResult<Message>, format: Format) -> i32 {
+    let code = result
+        .as_ref()
+        .map_or_else(|error| error.exit_code(), |_| 0);
+
+    match format {
+        Format::Text | Format::Md => match result {
+            Ok(Message::Empty) => (),
+            Ok(message) => {
+                let message = Output::Message(message).to_string();
+                if !message.is_empty() {
+                    println!("{}", message);
+                }
+            }
+            Err(error) => eprintln!("{}", Output::Error(error)),
+        },
+        Format::Json => {
+            // messages are only coloured for the terminal
+            colored::control::set_override(false);
+            let output = match result {
+                Ok(message) => json!({ "ok": true, "result": message.to_json() }),
+                Err(error) => json!({
+                    "ok": false,
+                    "error": {
+                        "kind": error.kind().name(),
+                        "code": error.exit_code(),
+                        "message": error.to_string(),
+                    }
+                }),
+            };
+            colored::control::unset_override();
+            println!("{}", output);
+        }
+    }
+
+    code
+}
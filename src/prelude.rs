@@ -5,6 +5,7 @@ pub use crate::{
     fileio::*,
     items::*,
     jeff_path::*,
+    markdown::*,
     output::{error::Error, Error::*, *},
     state::*,
     tests::*,
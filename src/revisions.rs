@@ -0,0 +1,280 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::Serialize;
+use std::fs::{copy, create_dir_all, read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use crate::{git, prelude::*};
+
+const SNAPSHOT_ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// An earlier version of a note.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    /// commit hash, or snapshot name
+    pub id: String,
+    pub time: DateTime<Local>,
+    /// commit subject, or the size of the snapshot
+    pub summary: String,
+    /// path to the note inside of the commit (relative to the top of the
+    /// repository), or to the snapshot file
+    location: String,
+}
+
+enum Store {
+    /// the root of the repository's work tree
+    Git(PathBuf),
+    /// folder holding the note's snapshots
+    Snapshots(PathBuf),
+}
+
+/**
+ * Earlier versions of a note. Notes tracked by a git repository at the root
+ * of the vault use its history. Other notes use snapshots in
+ * `<vault>/.jeff/snapshots/<note path>/`, taken whenever the editor closes.
+ */
+pub struct NoteRevisions {
+    note: PathBuf,
+    store: Store,
+}
+
+impl NoteRevisions {
+    pub fn open(vault_path: &Path, note: &Path) -> Self {
+        // a repository above the vault may not be the vault's own
+        let vault_repo =
+            git::toplevel(vault_path).is_ok_and(|toplevel| same_path(&toplevel, vault_path));
+        let store = if vault_repo && git::is_tracked(vault_path, note).unwrap_or(false) {
+            Store::Git(vault_path.to_path_buf())
+        } else {
+            let relative_path = note.strip_prefix(vault_path).unwrap_or(note);
+            Store::Snapshots(vault_path.join(".jeff/snapshots").join(relative_path))
+        };
+
+        NoteRevisions {
+            note: note.to_path_buf(),
+            store,
+        }
+    }
+
+    /// Returns every revision, newest first.
+    pub fn revisions(&self) -> JeffResult<Vec<Revision>> {
+        match &self.store {
+            Store::Git(repo) => Ok(git::file_log(repo, &self.note)?
+                .into_iter()
+                .map(|commit| Revision {
+                    id: commit.hash,
+                    time: commit.time,
+                    summary: commit.subject,
+                    location: commit.path,
+                })
+                .collect()),
+            Store::Snapshots(folder) => {
+                if !folder.is_dir() {
+                    return Ok(vec![]);
+                }
+
+                let mut revisions = vec![];
+                for entry in read_dir(folder)?.flatten() {
+                    let path = entry.path();
+                    let Some(id) = path.file_stem().map(|id| id.to_string_lossy().to_string())
+                    else {
+                        continue;
+                    };
+                    let Some(time) = id
+                        .get(..15)
+                        .and_then(|time| {
+                            NaiveDateTime::parse_from_str(time, SNAPSHOT_ID_FORMAT).ok()
+                        })
+                        .and_then(|time| Local.from_local_datetime(&time).earliest())
+                    else {
+                        continue;
+                    };
+
+                    revisions.push(Revision {
+                        summary: format!("{} lines", read_to_string(&path)?.lines().count()),
+                        id,
+                        time,
+                        location: path.display().to_string(),
+                    });
+                }
+                revisions.sort_by(|a, b| b.id.cmp(&a.id));
+
+                Ok(revisions)
+            }
+        }
+    }
+
+    /// Returns the revision with the given id. Ids can be abbreviated.
+    pub fn find(&self, id: &str) -> JeffResult<Revision> {
+        let revisions = self.revisions()?;
+        let exact = revisions.iter().find(|revision| revision.id == id);
+        let prefixed = || {
+            revisions
+                .iter()
+                .find(|revision| !id.is_empty() && revision.id.starts_with(id))
+        };
+
+        exact
+            .or_else(prefixed)
+            .cloned()
+            .ok_or_else(|| Error::RevisionNotFound(id.to_owned()))
+    }
+
+    /// Returns the newest revision that differs from the note's current
+    /// contents.
+    pub fn last_change(&self) -> JeffResult<Option<Revision>> {
+        let current = read_to_string(&self.note)?;
+        for revision in self.revisions()? {
+            if self.contents(&revision)? != current {
+                return Ok(Some(revision));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn contents(&self, revision: &Revision) -> JeffResult<String> {
+        match &self.store {
+            Store::Git(repo) => git::show(repo, &revision.id, &revision.location),
+            Store::Snapshots(_) => Ok(read_to_string(&revision.location)?),
+        }
+    }
+
+    /// Takes a snapshot of the note, unless it is tracked by git or hasn't
+    /// changed since the last snapshot.
+    pub fn snapshot(&self) -> JeffResult<()> {
+        let Store::Snapshots(folder) = &self.store else {
+            return Ok(());
+        };
+
+        let current = read_to_string(&self.note)?;
+        if let Some(last) = self.revisions()?.first() {
+            if self.contents(last)? == current {
+                return Ok(());
+            }
+        }
+
+        create_dir_all(folder)?;
+        let timestamp = Local::now().format(SNAPSHOT_ID_FORMAT);
+        let mut path = folder.join(format!("{}.md", timestamp));
+        let mut count = 1;
+        while path.exists() {
+            path = folder.join(format!("{}-{}.md", timestamp, count));
+            count += 1;
+        }
+        copy(&self.note, path)?;
+
+        Ok(())
+    }
+
+    /// Keeps the note's current contents as a revision: notes tracked by git
+    /// are committed with `message` if they changed, other notes get a
+    /// snapshot.
+    pub fn save(&self, message: &str) -> JeffResult<()> {
+        match &self.store {
+            Store::Git(repo) => git::commit(repo, std::slice::from_ref(&self.note), message),
+            Store::Snapshots(_) => self.snapshot(),
+        }
+    }
+}
+
+/// Whether two paths lead to the same folder, once symbolic links are
+/// resolved.
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// A line in the difference between two texts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", content = "text", rename_all = "lowercase")]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Returns the lines that were removed from `old` and added in `new`, using
+/// their longest common subsequence.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lengths[i][j]: longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = vec![];
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            lines.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+
+    lines
+}
+
+#[test]
+fn diff_lines_tests() {
+    use DiffLine::*;
+
+    assert_eq!(
+        vec![
+            Same("a".to_string()),
+            Removed("b".to_string()),
+            Added("c".to_string()),
+            Same("d".to_string()),
+            Added("e".to_string()),
+        ],
+        diff_lines("a\nb\nd", "a\nc\nd\ne")
+    );
+    assert!(diff_lines("a\nb", "a\nb")
+        .iter()
+        .all(|line| matches!(line, Same(_))));
+    assert_eq!(vec![Removed("a".to_string())], diff_lines("a", ""));
+}
+
+#[test]
+fn snapshot_tests() {
+    let vault = std::env::temp_dir().join(format!("jeff_revisions_{}", std::process::id()));
+    let note = vault.join("folder/note.md");
+    create_dir_all(note.parent().unwrap()).unwrap();
+    std::fs::write(&note, "first").unwrap();
+
+    let revisions = NoteRevisions::open(&vault, &note);
+    assert!(revisions.revisions().unwrap().is_empty());
+
+    revisions.snapshot().unwrap();
+    revisions.snapshot().unwrap(); // unchanged, so no new snapshot
+    std::fs::write(&note, "second").unwrap();
+    revisions.snapshot().unwrap();
+
+    let all = revisions.revisions().unwrap();
+    assert_eq!(2, all.len());
+    assert!(vault.join(".jeff/snapshots/folder/note.md").is_dir());
+    assert_eq!("second", revisions.contents(&all[0]).unwrap());
+    assert_eq!("first", revisions.contents(&all[1]).unwrap());
+    assert_eq!(all[1].id, revisions.find(&all[1].id).unwrap().id);
+    assert!(revisions.find("missing").is_err());
+    assert_eq!(all[1].id, revisions.last_change().unwrap().unwrap().id);
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
@@ -0,0 +1,221 @@
+/**
+ * Interactive shell that keeps a single [App] alive between commands.
+ */
+use std::{iter::once, path::PathBuf};
+
+use clap::{CommandFactory, Parser};
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    history::DefaultHistory,
+    Context, Editor as LineEditor, Helper, Highlighter, Hinter, Validator,
+};
+
+use crate::{app::App, prelude::*};
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ShellHelper {
+    /// names and aliases of every command
+    commands: Vec<String>,
+    /// names of the vaults, templates, and items in the current folder
+    names: Vec<String>,
+}
+
+impl ShellHelper {
+    fn new() -> Self {
+        let mut commands: Vec<String> = Args::command()
+            .get_subcommands()
+            .flat_map(|command| once(command.get_name()).chain(command.get_all_aliases()))
+            .map(|name| name.to_string())
+            .collect();
+        commands.push("exit".to_string());
+
+        ShellHelper {
+            commands,
+            names: vec![],
+        }
+    }
+
+    /// Returns the start of the word being completed and the candidates for it.
+    /// The first word is completed as a command, every other word as the name
+    /// of an item.
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |space| space + 1);
+        let word = line[start..].trim_start_matches(['"', '\'']);
+
+        let options = if line[..start].trim().is_empty() {
+            &self.commands
+        } else {
+            &self.names
+        };
+
+        let mut candidates: Vec<String> = options
+            .iter()
+            .filter(|option| option.starts_with(word))
+            .cloned()
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        (start, candidates)
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = self.candidates(line, pos);
+        let pairs = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                replacement: if candidate.contains(char::is_whitespace) {
+                    format!("\"{}\"", candidate)
+                } else {
+                    candidate.to_owned()
+                },
+                display: candidate,
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+pub struct Shell {
+    editor: LineEditor<ShellHelper, DefaultHistory>,
+}
+
+impl Shell {
+    pub fn new() -> JeffResult<Self> {
+        let mut editor = LineEditor::new()?;
+        editor.set_helper(Some(ShellHelper::new()));
+        // The history file does not exist the first time the shell is run.
+        let _ = editor.load_history(&Shell::history_path());
+
+        Ok(Shell { editor })
+    }
+
+    fn history_path() -> PathBuf {
+        let mut path = application_data_path();
+        path.push("shell_history");
+        path
+    }
+
+    fn prompt(app: &App) -> String {
+        match app.location() {
+            Some((vault, Some(folder))) => format!("{} > {} ϟ ", vault, folder),
+            Some((vault, None)) => format!("{} ϟ ", vault),
+            None => "ϟ ".to_string(),
+        }
+    }
+
+    /// Splits `line` into words, the way a shell would.
+    fn split_line(line: &str) -> JeffResult<Vec<String>> {
+        shlex::split(line).ok_or_else(|| Error::InvalidInput(line.to_owned()))
+    }
+
+    /// Reads and runs commands until `exit` or end of input.
+    pub fn run(&mut self, app: &mut App) -> JeffResult<()> {
+        loop {
+            if let Some(helper) = self.editor.helper_mut() {
+                helper.names = app.item_names();
+            }
+
+            let line = match self.editor.readline(&Shell::prompt(app)) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(error) => return Err(error.into()),
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+            self.editor.add_history_entry(line.as_str())?;
+
+            let words = match Shell::split_line(&line) {
+                Ok(words) => words,
+                Err(error) => {
+                    eprintln!("{}", Output::Error(error));
+                    continue;
+                }
+            };
+
+            // a line with only a comment has no words
+            if words.is_empty() {
+                continue;
+            }
+            if words[0] == "exit" || words[0] == "quit" {
+                break;
+            }
+
+            let Args {
+                command,
+                format,
+                yes,
+                no_input,
+            } = match Args::try_parse_from(once("jf".to_string()).chain(words)) {
+                Ok(args) => args,
+                Err(error) => {
+                    let _ = error.print();
+                    continue;
+                }
+            };
+
+            if let Err(error) = command.check_format(format) {
+                eprintln!("{}", Output::Error(error));
+                continue;
+            }
+            if let Command::Shell = command {
+                eprintln!("{}", Output::Error(Error::AlreadyInShell));
+                continue;
+            }
+
+            app.set_prompts(Prompts::new(yes, no_input));
+            app.set_command_line(format!("jf {}", line.trim()));
+            let is_mutating = command.is_mutating();
+            display_result(app.handle_command(command), format);
+
+            if is_mutating {
+                if let Err(error) = app.reload() {
+                    eprintln!("{}", Output::Error(error));
+                }
+            }
+        }
+
+        self.editor.save_history(&Shell::history_path())?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn completion_tests() {
+    let mut helper = ShellHelper::new();
+    helper.names = vec!["notes".to_string(), "note one".to_string()];
+
+    assert_eq!((0, vec!["note".to_string()]), helper.candidates("note", 4));
+    assert!(helper.candidates("e", 1).1.contains(&"exit".to_string()));
+    assert_eq!(
+        (5, vec!["note one".to_string(), "notes".to_string()]),
+        helper.candidates("open no", 7)
+    );
+    assert_eq!((5, vec![]), helper.candidates("open x", 6));
+}
+
+#[test]
+fn split_line_tests() {
+    assert_eq!(
+        vec!["open".to_string(), "note one".to_string()],
+        Shell::split_line("open 'note one'").unwrap()
+    );
+    assert!(Shell::split_line("# note").unwrap().is_empty());
+    assert!(Shell::split_line("open 'note").is_err());
+}
@@ -1,4 +1,7 @@
-use crate::enums::{ConfigType, Item as ItemType, VaultItem as VaultItemType};
+use crate::enums::{ConfigType, Format, Item as ItemType, LinkStyle, VaultItem as VaultItemType};
+use crate::output::error::{Error, JeffResult};
+use crate::utils::{parse_age, parse_since};
+use chrono::{DateTime, Duration, Local};
 use clap::{AppSettings, Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -24,32 +27,70 @@ interact with items
     \x1b[0;34mchdir\x1b[0m, \x1b[0;34mcd\x1b[0m       change folder within current vault
     \x1b[0;34mlist\x1b[0m, \x1b[0;34mls\x1b[0m        print dir tree of current folder
     \x1b[0;34mtoday\x1b[0m, \x1b[0;34mto\x1b[0m       edit daily note
+    \x1b[0;34mappend\x1b[0m, \x1b[0;34map\x1b[0m      append text to a note
+    \x1b[0;34mprepend\x1b[0m, \x1b[0;34mpp\x1b[0m     prepend text to a note
+    \x1b[0;34mcapture\x1b[0m, \x1b[0;34mc\x1b[0m      capture text to the inbox
+    \x1b[0;34mlog\x1b[0m, \x1b[0;34mlg\x1b[0m         list the earlier versions of a note
+    \x1b[0;34mdiff\x1b[0m, \x1b[0;34mdf\x1b[0m        compare a note with an earlier version
+    \x1b[0;34mrestore\x1b[0m, \x1b[0;34mrs\x1b[0m     roll a note back to an earlier version
+    \x1b[0;34mattach\x1b[0m, \x1b[0;34mat\x1b[0m      copy a file into the vault and embed it in a note
+    \x1b[0;34mattachments\x1b[0m, \x1b[0;34mas\x1b[0m list attachments, orphans, or missing embeds
 
 perform fs operations on items
     \x1b[0;34mremove\x1b[0m, \x1b[0;34mrm\x1b[0m      remove an item 
     \x1b[0;34mrename\x1b[0m, \x1b[0;34mrn\x1b[0m      rename an item 
     \x1b[0;34mmove\x1b[0m, \x1b[0;34mmv\x1b[0m        move an item to a new location
     \x1b[0;34mvmove\x1b[0m, \x1b[0;34mvm\x1b[0m       move an item to a different vault
+    \x1b[0;34mtrash\x1b[0m, \x1b[0;34mtr\x1b[0m       list, restore, or empty removed items
+    \x1b[0;34mundo\x1b[0m, \x1b[0;34mun\x1b[0m        undo the last fs operations
+    \x1b[0;34mhistory\x1b[0m, \x1b[0;34mhs\x1b[0m     show the commands that changed files
+    \x1b[0;34mdoctor\x1b[0m, \x1b[0;34mdr\x1b[0m      check vaults for broken links and other problems
+    \x1b[0;34mexport\x1b[0m, \x1b[0;34mex\x1b[0m      export the current vault as a website, or notes as one document
+
+    \x1b[0;34mshell\x1b[0m, \x1b[0;34msh\x1b[0m       start an interactive shell
+    \x1b[0;34mtui\x1b[0m, \x1b[0;34mui\x1b[0m         browse the current vault
 
 config
     \x1b[0;34mconfig\x1b[0m, \x1b[0;34mcf\x1b[0m      set and get config values
     \x1b[0;34mtemplate\x1b[0m, \x1b[0;34mtp\x1b[0m    create or edit a note template
 
+output and prompts
+    use \x1b[0;34m--format json\x1b[0m with any command to get json output
+    use \x1b[0;34m--yes\x1b[0m to confirm every prompt, or \x1b[0;34m--no-input\x1b[0m to fail instead of prompting
+
 get help 
     use \x1b[0;34mhelp\x1b[0m or \x1b[0;34m-h\x1b[0m and \x1b[0;34m--help\x1b[0m flags along with a command to get corresponding help"))]
 pub struct Args {
     #[clap(subcommand)]
     pub command: Command,
+    /// confirm every prompt
+    #[clap(parse(from_flag), short = 'y', long = "yes", global = true)]
+    pub yes: bool,
+    /// never prompt for input, fail instead (also the case when stdin is not a terminal)
+    #[clap(parse(from_flag), long = "no-input", global = true)]
+    pub no_input: bool,
+    /// output format, text or json (or md for export bundle)
+    #[clap(
+        value_enum,
+        value_parser,
+        long = "format",
+        global = true,
+        default_value = "text"
+    )]
+    pub format: Format,
 }
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum Command {
     /// create a vault or list vaults
     #[clap(override_usage(
-        "jf vault\n    jf vault -l\n    jf vault <vault name> <vault location>"
+        "jf vault\n    jf vault -l\n    jf vault <vault name> <vault location>\n    jf vault import <vault name> <path>\n    jf vault relink <vault name> <new path>\n    jf vault archive <vault name> <file.tar.gz|zip>\n    jf vault restore <archive> [vault name] [location]"
     ))]
     #[clap(alias = "vl")]
+    #[clap(args_conflicts_with_subcommands = true)]
     Vault {
+        #[clap(subcommand)]
+        action: Option<VaultAction>,
         /// show vaults' location
         #[clap(parse(from_flag), short = 'l', long = "location")]
         show_loc: bool,
@@ -74,7 +115,7 @@ pub enum Command {
     },
     /// create a note
     #[clap(override_usage(
-        "jf note\n    jf note <note name>\n    jf note <note name> -t <template name>"
+        "jf note\n    jf note <note name>\n    jf note <note name> -t <template name>\n    jf note <note name> --ext <extension>"
     ))]
     #[clap(alias = "nt")]
     Note {
@@ -87,6 +128,10 @@ pub enum Command {
         /// name of the note template
         #[clap(value_parser, name = "template name")]
         template_name: Option<String>,
+        /// file extension of the new note (md, markdown, txt, org or adoc),
+        /// instead of the vault's default
+        #[clap(long = "ext", value_parser, name = "extension")]
+        extension: Option<String>,
     },
     /// create or edit a template, or list existing templates
     #[clap(override_usage("jf tempate\n    jf template [template-name]"))]
@@ -119,6 +164,52 @@ pub enum Command {
         #[clap(value_parser, name = "note name")]
         name: String,
     },
+    /// append text to a note (from the current folder)
+    #[clap(override_usage(
+        "jf append <note name> [text]\n    jf append <note name> [text] --under <heading>"
+    ))]
+    #[clap(alias = "ap")]
+    Append {
+        /// name of the note to append to
+        #[clap(value_parser, name = "note name")]
+        name: String,
+        /// text to append (read from stdin if omitted or "-")
+        #[clap(value_parser, name = "text", allow_hyphen_values = true)]
+        text: Option<String>,
+        /// append to the end of the section under this heading
+        #[clap(value_parser, short = 'u', long = "under", name = "heading")]
+        under: Option<String>,
+        /// prefix the text with the current time
+        #[clap(parse(from_flag), short = 's', long = "timestamp")]
+        timestamp: bool,
+    },
+    /// prepend text to a note (from the current folder)
+    #[clap(override_usage(
+        "jf prepend <note name> [text]\n    jf prepend <note name> [text] --under <heading>"
+    ))]
+    #[clap(alias = "pp")]
+    Prepend {
+        /// name of the note to prepend to
+        #[clap(value_parser, name = "note name")]
+        name: String,
+        /// text to prepend (read from stdin if omitted or "-")
+        #[clap(value_parser, name = "text", allow_hyphen_values = true)]
+        text: Option<String>,
+        /// prepend to the start of the section under this heading
+        #[clap(value_parser, short = 'u', long = "under", name = "heading")]
+        under: Option<String>,
+        /// prefix the text with the current time
+        #[clap(parse(from_flag), short = 's', long = "timestamp")]
+        timestamp: bool,
+    },
+    /// append a timestamped bullet to the inbox note (the daily note by default)
+    #[clap(override_usage("jf capture [text]"))]
+    #[clap(alias = "c")]
+    Capture {
+        /// text to capture (read from stdin if omitted or "-")
+        #[clap(value_parser, name = "text", allow_hyphen_values = true)]
+        text: Option<String>,
+    },
     /// create a folder
     #[clap(override_usage("jf folder\n    jf folder [folder name]"))]
     #[clap(alias = "fd")]
@@ -144,6 +235,108 @@ pub enum Command {
         #[clap(value_parser, name = "name")]
         name: String,
     },
+    /// undo the last create, remove, rename, move, or vmove operations
+    #[clap(override_usage("jf undo [count]"))]
+    #[clap(alias = "un")]
+    Undo {
+        /// number of operations to undo
+        #[clap(value_parser, name = "count", default_value = "1")]
+        count: usize,
+    },
+    /// show the commands that changed files, oldest first
+    #[clap(override_usage("jf history [--vault <name>] [--since <date>]"))]
+    #[clap(alias = "hs")]
+    History {
+        /// only show commands run in or changing this vault
+        #[clap(long = "vault", name = "name")]
+        vault: Option<String>,
+        /// only show commands run since this date (YYYY-MM-DD) or age, e.g. 2d
+        #[clap(long = "since", name = "date", value_parser = parse_since)]
+        since: Option<DateTime<Local>>,
+    },
+    /// check the current vault and the registered vaults for problems
+    #[clap(alias = "dr")]
+    Doctor {
+        /// fix the problems that can be fixed safely
+        #[clap(long = "fix")]
+        fix: bool,
+    },
+    /// export the current vault
+    #[clap(override_usage(
+        "jf export html <out dir>\n    jf export bundle <note|folder> [--inline-links] [--format md|json]"
+    ))]
+    #[clap(alias = "ex")]
+    Export {
+        #[clap(subcommand)]
+        action: ExportAction,
+    },
+    /// list the earlier versions of a note, newest first
+    #[clap(alias = "lg")]
+    Log {
+        /// name of the note
+        #[clap(value_parser, name = "note name")]
+        name: String,
+    },
+    /// compare a note with an earlier version
+    #[clap(override_usage("jf diff <note name> [revision]"))]
+    #[clap(alias = "df")]
+    Diff {
+        /// name of the note
+        #[clap(value_parser, name = "note name")]
+        name: String,
+        /// commit or snapshot to compare with (the last change by default)
+        #[clap(value_parser, name = "revision")]
+        revision: Option<String>,
+    },
+    /// roll a note back to an earlier version
+    #[clap(alias = "rs")]
+    Restore {
+        /// name of the note
+        #[clap(value_parser, name = "note name")]
+        name: String,
+        /// commit or snapshot to restore
+        #[clap(value_parser, name = "revision")]
+        revision: String,
+    },
+    /// copy a file into the vault's attachment folder and embed it in a note
+    #[clap(override_usage("jf attach <note name> <file> [--link markdown|wiki]"))]
+    #[clap(alias = "at")]
+    Attach {
+        /// name of the note (from the current folder)
+        #[clap(value_parser, name = "note name")]
+        name: String,
+        /// file to attach
+        #[clap(value_parser, name = "file")]
+        file: PathBuf,
+        /// style of the embed (wiki links in Obsidian vaults, markdown otherwise)
+        #[clap(value_enum, long = "link", name = "style")]
+        link: Option<LinkStyle>,
+    },
+    /// list the attachments of the current vault
+    #[clap(override_usage(
+        "jf attachments\n    jf attachments --orphans\n    jf attachments --missing"
+    ))]
+    #[clap(alias = "as")]
+    Attachments {
+        /// only list attachments that no note links to
+        #[clap(parse(from_flag), long = "orphans")]
+        orphans: bool,
+        /// list embeds whose file doesn't exist instead
+        #[clap(parse(from_flag), long = "missing", conflicts_with = "orphans")]
+        missing: bool,
+    },
+    /// list, restore, or empty removed items
+    #[clap(override_usage(
+        "jf trash [list]\n    jf trash restore <item>\n    jf trash empty [--older-than <age>]"
+    ))]
+    #[clap(alias = "tr")]
+    Trash {
+        #[clap(subcommand)]
+        action: Option<TrashAction>,
+        /// use the trash of removed vaults instead of the current vault's
+        #[clap(parse(from_flag), long = "vaults", global = true)]
+        vaults: bool,
+    },
     /// rename an item
     #[clap(alias = "rn")]
     Rename {
@@ -199,6 +392,142 @@ pub enum Command {
         #[clap(value_parser, name = "config value")]
         value: Option<String>,
     },
+    /// start an interactive shell
+    #[clap(alias = "sh")]
+    Shell,
+    /// browse the current vault in a full-screen terminal ui
+    #[clap(alias = "ui")]
+    Tui,
     /// 🆘 show this help message or help for given command.
     Help,
 }
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum VaultAction {
+    /// register an existing folder (e.g. an Obsidian vault) as a vault
+    #[clap(override_usage("jf vault import <vault name> <path>"))]
+    Import {
+        /// name for the vault
+        #[clap(value_parser, name = "vault name")]
+        name: String,
+        /// path to the existing folder
+        #[clap(value_parser, name = "path")]
+        path: PathBuf,
+    },
+    /// point a vault to the folder it was moved to, without moving files
+    #[clap(override_usage("jf vault relink <vault name> <new path>"))]
+    Relink {
+        /// name of the vault
+        #[clap(value_parser, name = "vault name")]
+        name: String,
+        /// path to the vault's folder
+        #[clap(value_parser, name = "new path")]
+        path: PathBuf,
+    },
+    /// write a vault, with jeff's data, to a .tar.gz or .zip archive
+    #[clap(override_usage("jf vault archive <vault name> <file.tar.gz|zip>"))]
+    Archive {
+        /// name of the vault
+        #[clap(value_parser, name = "vault name")]
+        name: String,
+        /// path to the archive to write
+        #[clap(value_parser, name = "archive")]
+        archive: PathBuf,
+    },
+    /// extract a vault archive and register the vault
+    #[clap(override_usage("jf vault restore <archive> [vault name] [location]"))]
+    Restore {
+        /// path to the .tar.gz or .zip archive
+        #[clap(value_parser, name = "archive")]
+        archive: PathBuf,
+        /// name for the vault, the name of the archived folder by default
+        #[clap(value_parser, name = "vault name")]
+        name: Option<String>,
+        /// folder to extract the vault into, the working directory by default
+        #[clap(value_parser, name = "location")]
+        location: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ExportAction {
+    /// render every note to HTML, with a folder index, tag pages and search
+    Html {
+        /// folder to write the website to, outside of the vault
+        #[clap(value_parser, name = "out dir")]
+        out: PathBuf,
+    },
+    /// print a note, or a folder's notes, as one markdown or json document
+    Bundle {
+        /// name of the note or folder in the current folder
+        #[clap(value_parser, name = "note|folder")]
+        name: String,
+        /// turn wiki links to bundled notes into links to their headings
+        #[clap(long = "inline-links")]
+        inline_links: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TrashAction {
+    /// list removed items
+    #[clap(alias = "ls")]
+    List,
+    /// restore a removed item to where it was removed from
+    Restore {
+        /// name of the item to restore
+        #[clap(value_parser, name = "item")]
+        item: String,
+    },
+    /// permanently delete removed items
+    Empty {
+        /// only delete items removed longer ago than this, e.g. 30d, 12h, 2w
+        #[clap(long = "older-than", name = "age", value_parser = parse_age)]
+        older_than: Option<Duration>,
+    },
+}
+
+impl Command {
+    /// Whether the command can create, change, or remove files.
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            Command::Vault {
+                action: None,
+                location: None,
+                ..
+            } | Command::Enter { .. }
+                | Command::Open { .. }
+                | Command::Chdir { .. }
+                | Command::List
+                | Command::Config { .. }
+                | Command::Trash {
+                    action: None | Some(TrashAction::List),
+                    ..
+                }
+                | Command::History { .. }
+                | Command::Doctor { fix: false }
+                | Command::Export { .. }
+                | Command::Log { .. }
+                | Command::Diff { .. }
+                | Command::Attachments { .. }
+                | Command::Shell
+                | Command::Help
+        )
+    }
+
+    /// Checks that the command can be printed in `format`. Only `export
+    /// bundle` prints markdown.
+    pub fn check_format(&self, format: Format) -> JeffResult<()> {
+        match (self, format) {
+            (
+                Command::Export {
+                    action: ExportAction::Bundle { .. },
+                },
+                Format::Md,
+            )
+            | (_, Format::Text | Format::Json) => Ok(()),
+            (_, Format::Md) => Err(Error::UnsupportedFormat("md".to_string())),
+        }
+    }
+}
@@ -4,8 +4,12 @@ use std::path::PathBuf;
 use colored::Color;
 use serde::{Deserialize, Serialize};
 
+use crate::output::error::JeffResult;
 use crate::utils::application_config_path;
-use crate::{enums::ConfigType, fileio::FileIO};
+use crate::{
+    enums::ConfigType,
+    fileio::{FileIO, Migration},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditorData {
@@ -22,21 +26,28 @@ impl Default for EditorData {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    schema_version: u32,
     vault_color: String,
     folder_color: String,
     note_color: String,
+    /// note that `jf capture` appends to, relative to the vault root.
+    /// The daily note is used if it is not set.
+    #[serde(default)]
+    inbox: Option<String>,
     editor_data: EditorData,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
+            schema_version: Config::SCHEMA_VERSION,
             editor_data: EditorData::default(),
             vault_color: "red".to_string(),
             folder_color: "blue".to_string(),
             note_color: "yellow".to_string(),
+            inbox: None,
         }
     }
 }
@@ -48,11 +59,20 @@ impl Display for Config {
 }
 
 impl FileIO for Config {
+    const SCHEMA_VERSION: u32 = 1;
+
     fn path(&self) -> PathBuf {
         let mut path = application_config_path();
         path.push("config");
         path
     }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            // 0 -> 1: added schema_version
+            |_| (),
+        ]
+    }
 }
 
 impl Config {
@@ -60,16 +80,17 @@ impl Config {
         self.editor_data.clone()
     }
 
-    pub fn set_config_value(&mut self, config_type: &ConfigType, value: String) {
-        match config_type {
-            ConfigType::Editor => self.editor_data.editor = value,
-            ConfigType::Conflict => self.editor_data.conflict = value == "true".to_string(),
-            ConfigType::VaultColor => self.vault_color = value,
-            ConfigType::FolderColor => self.folder_color = value,
-            ConfigType::NoteColor => self.note_color = value,
-        }
-
-        self.store()
+    pub fn set_config_value(&mut self, config_type: &ConfigType, value: String) -> JeffResult<()> {
+        self.update(|config| match config_type {
+            ConfigType::Editor => config.editor_data.editor = value,
+            ConfigType::Conflict => config.editor_data.conflict = value == "true".to_string(),
+            ConfigType::VaultColor => config.vault_color = value,
+            ConfigType::FolderColor => config.folder_color = value,
+            ConfigType::NoteColor => config.note_color = value,
+            ConfigType::Inbox => config.inbox = Some(value).filter(|inbox| !inbox.is_empty()),
+            // stored in the current vault
+            ConfigType::GitAutocommit | ConfigType::NoteExtensions | ConfigType::NoteExtension => {}
+        })
     }
 
     #[allow(unused)]
@@ -80,9 +101,20 @@ impl Config {
             ConfigType::VaultColor => self.vault_color.to_string(),
             ConfigType::FolderColor => self.folder_color.to_string(),
             ConfigType::NoteColor => self.note_color.to_string(),
+            ConfigType::Inbox => self
+                .inbox
+                .clone()
+                .unwrap_or_else(|| "daily note".to_string()),
+            ConfigType::GitAutocommit | ConfigType::NoteExtensions | ConfigType::NoteExtension => {
+                String::new()
+            }
         }
     }
 
+    pub fn get_inbox(&self) -> Option<String> {
+        self.inbox.clone()
+    }
+
     pub fn get_vault_color(&self) -> Color {
         Color::try_from(self.vault_color.to_owned()).unwrap_or(Color::Red)
     }
@@ -1,19 +1,55 @@
 use crate::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use toml::{value::Table, Value};
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Data {
+    schema_version: u32,
     current: Option<String>,
+    /// absolute path to each vault, by name
     vaults: HashMap<String, PathBuf>,
 }
 
+impl Default for Data {
+    fn default() -> Self {
+        Data {
+            schema_version: Data::SCHEMA_VERSION,
+            current: None,
+            vaults: HashMap::new(),
+        }
+    }
+}
+
 impl FileIO for Data {
+    const SCHEMA_VERSION: u32 = 2;
+
     fn path(&self) -> PathBuf {
         let mut path = application_data_path();
         path.push("vaults");
         path
     }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            // 0 -> 1: added schema_version
+            |_| (),
+            // 1 -> 2: vaults store their own path instead of the folder
+            // containing them, so that their folder can have any name
+            |data: &mut Table| {
+                if let Some(Value::Table(vaults)) = data.get_mut("vaults") {
+                    for (name, location) in vaults.iter_mut() {
+                        if let Value::String(location) = location {
+                            *location = PathBuf::from(&location).join(name).display().to_string();
+                        }
+                    }
+                }
+            },
+        ]
+    }
 }
 
 impl Data {
@@ -21,16 +57,15 @@ impl Data {
         self.current.as_ref()
     }
 
-    pub fn set_current_vault(&mut self, vault: Option<String>) {
-        self.current = vault;
-        self.store()
+    pub fn set_current_vault(&mut self, vault: Option<String>) -> JeffResult<()> {
+        self.update(|data| data.current = vault)
     }
 
     pub fn get_vaults(&self) -> &HashMap<String, PathBuf> {
         &self.vaults
     }
 
-    pub fn get_vault_location(&self, name: &str) -> Option<&PathBuf> {
+    pub fn get_vault_path(&self, name: &str) -> Option<&PathBuf> {
         self.vaults.get(name)
     }
 
@@ -38,24 +73,42 @@ impl Data {
         self.vaults.contains_key(name)
     }
 
-    pub fn add_vault(&mut self, name: String, location: PathBuf) {
-        self.vaults.insert(name, location);
-        self.store()
+    /// Returns the name of the vault at `path`.
+    pub fn vault_name(&self, path: &Path) -> Option<&String> {
+        self.vaults
+            .iter()
+            .find(|(_, vault_path)| vault_path.as_path() == path)
+            .map(|(name, _)| name)
+    }
+
+    pub fn add_vault(&mut self, name: String, path: PathBuf) -> JeffResult<()> {
+        self.update(|data| {
+            data.vaults.insert(name, path);
+        })
     }
 
-    pub fn remove_vault(&mut self, name: &str) {
-        self.vaults.remove(name);
-        self.store()
+    pub fn remove_vault(&mut self, name: &str) -> JeffResult<()> {
+        self.update(|data| {
+            data.vaults.remove(name);
+        })
     }
 
-    pub fn rename_vault(&mut self, name: &str, new_name: String) {
-        let value = self.vaults.remove(name);
-        self.vaults.insert(new_name, value.unwrap());
-        self.store()
+    pub fn rename_vault(
+        &mut self,
+        name: &str,
+        new_name: String,
+        new_path: PathBuf,
+    ) -> JeffResult<()> {
+        self.update(|data| {
+            if data.vaults.remove(name).is_some() {
+                data.vaults.insert(new_name, new_path);
+            }
+        })
     }
 
-    pub fn set_vault_location(&mut self, name: &str, new_location: PathBuf) {
-        self.vaults.insert(name.to_owned(), new_location);
-        self.store()
+    pub fn set_vault_path(&mut self, name: &str, new_path: PathBuf) -> JeffResult<()> {
+        self.update(|data| {
+            data.vaults.insert(name.to_owned(), new_path);
+        })
     }
 }
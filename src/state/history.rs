@@ -0,0 +1,61 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{read_to_string, File};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+
+use crate::prelude::*;
+
+/// A mutating command, as recorded in the history log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub time: DateTime<Local>,
+    /// the current vault when the command was run
+    pub vault: Option<String>,
+    pub command: String,
+    /// absolute paths of the items the command changed
+    pub paths: Vec<PathBuf>,
+}
+
+/**
+ * Append-only log of every mutating command, one json object per line, in
+ * the application data folder. Unlike the journal it is never truncated.
+ */
+pub struct History;
+
+impl History {
+    fn path() -> PathBuf {
+        let mut path = application_data_path();
+        path.push("history");
+        path
+    }
+
+    pub fn append(entry: &HistoryEntry) -> JeffResult<()> {
+        let path = History::path();
+        let _lock = FileLock::acquire(&path)?;
+        let line = serde_json::to_string(entry).map_err(|_| Error::InternalError)?;
+
+        File::options()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|err| Error::FileError(path.display().to_string(), err))
+    }
+
+    /// Returns every entry, oldest first. Lines that can't be parsed are
+    /// skipped.
+    pub fn entries() -> JeffResult<Vec<HistoryEntry>> {
+        let path = History::path();
+        let contents = match read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(Error::FileError(path.display().to_string(), err)),
+        };
+
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
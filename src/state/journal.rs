@@ -0,0 +1,251 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{metadata, read_dir};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::prelude::*;
+
+/// Number of operations kept in the journal.
+const MAX_OPERATIONS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Create,
+    Remove,
+    Rename,
+    Move,
+    Vmove,
+}
+
+impl OperationKind {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            OperationKind::Create => "create",
+            OperationKind::Remove => "remove",
+            OperationKind::Rename => "rename",
+            OperationKind::Move => "move",
+            OperationKind::Vmove => "vmove",
+        }
+    }
+}
+
+/// A filesystem operation that can be undone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub item_type: ItemType,
+    /// absolute path to the item before the operation (none for creations)
+    pub before: Option<PathBuf>,
+    /// absolute path to the item after the operation (inside of the trash
+    /// for removals)
+    pub after: PathBuf,
+    pub time: DateTime<Local>,
+    /// fingerprint of the item right after the operation, used to check
+    /// that it hasn't changed since
+    fingerprint: String,
+}
+
+impl Operation {
+    pub fn new(
+        kind: OperationKind,
+        item_type: ItemType,
+        before: Option<PathBuf>,
+        after: PathBuf,
+    ) -> Self {
+        Operation {
+            kind,
+            item_type,
+            before,
+            fingerprint: fingerprint(&after).unwrap_or_default(),
+            after,
+            time: Local::now(),
+        }
+    }
+
+    /// Checks that the item is still as the operation left it.
+    pub fn is_unchanged(&self) -> bool {
+        fingerprint(&self.after).as_ref() == Some(&self.fingerprint)
+    }
+
+    /// Describes the operation, e.g. `rename note a -> b`.
+    pub fn describe(&self) -> String {
+        let item_type = self.item_type.full();
+        let before = self.before.as_deref().map(item_name);
+        let after = item_name(&self.after);
+
+        match (self.kind, before) {
+            (OperationKind::Create, _) | (_, None) => {
+                format!("{} {} {}", self.kind.to_str(), item_type, after)
+            }
+            (OperationKind::Remove, Some(before)) => {
+                format!("remove {} {}", item_type, before)
+            }
+            (OperationKind::Rename, Some(before)) => {
+                format!("rename {} {} -> {}", item_type, before, after)
+            }
+            (kind, Some(before)) => format!(
+                "{} {} {} -> {}",
+                kind.to_str(),
+                item_type,
+                before,
+                self.after
+                    .parent()
+                    .map_or(String::new(), |parent| parent.display().to_string())
+            ),
+        }
+    }
+}
+
+/// Name of the item at `path`, without a note extension.
+fn item_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+
+    Note::split_extension(&name).0.to_string()
+}
+
+/**
+ * Fingerprint of the files at `path`: their relative paths, sizes, and
+ * modification times. Hidden files and folders (like `.jeff`) are skipped.
+ * Returns `None` if nothing exists at `path`.
+ */
+fn fingerprint(path: &Path) -> Option<String> {
+    fn visit(root: &Path, path: &Path, entries: &mut Vec<String>) {
+        let Ok(file_metadata) = metadata(path) else {
+            return;
+        };
+
+        if file_metadata.is_dir() {
+            entries.push(format!("{}/", path.strip_prefix(root).unwrap().display()));
+            let Ok(children) = read_dir(path) else {
+                return;
+            };
+            for child in children.flatten() {
+                if !child.file_name().to_string_lossy().starts_with('.') {
+                    visit(root, &child.path(), entries);
+                }
+            }
+        } else {
+            let modified = file_metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |time| time.as_nanos());
+            entries.push(format!(
+                "{} {} {}",
+                path.strip_prefix(root).unwrap().display(),
+                file_metadata.len(),
+                modified
+            ));
+        }
+    }
+
+    if !path.exists() {
+        return None;
+    }
+
+    let mut entries = vec![];
+    visit(path, path, &mut entries);
+    entries.sort();
+
+    // FNV-1a, so that fingerprints stay the same between builds
+    let hash = entries
+        .join("\n")
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    Some(format!("{:016x}", hash))
+}
+
+/**
+ * The most recent filesystem operations, stored in the application data
+ * folder so that they can be undone.
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Journal {
+    schema_version: u32,
+    operations: Vec<Operation>,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Journal {
+            schema_version: Journal::SCHEMA_VERSION,
+            operations: vec![],
+        }
+    }
+}
+
+impl FileIO for Journal {
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn path(&self) -> PathBuf {
+        let mut path = application_data_path();
+        path.push("journal");
+        path
+    }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            // 0 -> 1: added schema_version
+            |_| (),
+        ]
+    }
+}
+
+impl Journal {
+    pub fn record(operation: Operation) -> JeffResult<()> {
+        Journal::load()?.update(|journal| {
+            journal.operations.push(operation);
+            let extra = journal.operations.len().saturating_sub(MAX_OPERATIONS);
+            journal.operations.drain(..extra);
+        })
+    }
+
+    pub fn last(&self) -> Option<&Operation> {
+        self.operations.last()
+    }
+
+    /// Removes the most recent operation.
+    pub fn pop(&mut self) -> JeffResult<()> {
+        self.update(|journal| {
+            journal.operations.pop();
+        })
+    }
+}
+
+#[test]
+fn journal_tests() {
+    let root = std::env::temp_dir().join(format!("jeff_journal_{}", std::process::id()));
+    let note = root.join("note.md");
+    std::fs::create_dir_all(root.join(".jeff")).unwrap();
+    std::fs::write(&note, "contents").unwrap();
+
+    let operation = Operation::new(
+        OperationKind::Rename,
+        ItemType::Nt,
+        Some(root.join("old.md")),
+        note.clone(),
+    );
+    assert_eq!("rename note old -> note", operation.describe());
+    assert_eq!("notes.txt", item_name(&root.join("notes.txt.org")));
+    assert_eq!("archive.zip", item_name(&root.join("archive.zip")));
+    assert!(operation.is_unchanged());
+
+    // hidden folders are ignored
+    let vault = Operation::new(OperationKind::Create, ItemType::Vl, None, root.clone());
+    std::fs::write(root.join(".jeff/data"), "current_folder = 'a'").unwrap();
+    assert!(vault.is_unchanged());
+
+    std::fs::write(&note, "new contents").unwrap();
+    assert!(!operation.is_unchanged());
+    assert!(!vault.is_unchanged());
+
+    std::fs::remove_dir_all(&root).unwrap();
+    assert!(!operation.is_unchanged());
+}
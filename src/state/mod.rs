@@ -1,9 +1,15 @@
 pub mod args;
 pub mod config;
 pub mod data;
+pub mod history;
+pub mod journal;
+pub mod trash;
 pub mod vaults;
 
 pub use args::*;
 pub use config::*;
+pub use history::*;
+pub use journal::*;
+pub use trash::*;
 pub use vaults::*;
 pub use vaults::*;
@@ -0,0 +1,327 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{copy, create_dir, create_dir_all, read_dir, remove_dir_all, remove_file, rename};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    /// name of the item inside of the trash folder
+    pub id: String,
+    pub item_type: ItemType,
+    /// where the item was removed from, relative to the vault
+    /// (absolute for removed vaults)
+    pub original_path: PathBuf,
+    pub removed_at: DateTime<Local>,
+}
+
+impl TrashItem {
+    /// Name of the item, without any extension.
+    pub fn name(&self) -> String {
+        let name = match self.item_type {
+            ItemType::Note | ItemType::Nt => self.original_path.file_stem(),
+            _ => self.original_path.file_name(),
+        };
+
+        name.map_or(String::new(), |name| name.to_string_lossy().to_string())
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.id == name || self.name() == name || self.original_path == Path::new(name)
+    }
+}
+
+/**
+ * Removed notes and folders, kept in `<vault>/.jeff/trash` until the trash
+ * is emptied. Removed vaults are kept in `<data dir>/trash`.
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Trash {
+    schema_version: u32,
+    /// absolute path to the trash folder
+    #[serde(skip)]
+    location: PathBuf,
+    items: Vec<TrashItem>,
+}
+
+impl Default for Trash {
+    fn default() -> Self {
+        Trash {
+            schema_version: Trash::SCHEMA_VERSION,
+            location: PathBuf::new(),
+            items: vec![],
+        }
+    }
+}
+
+impl FileIO for Trash {
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn path(&self) -> PathBuf {
+        self.location.join("index")
+    }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            // 0 -> 1: added schema_version
+            |_| (),
+        ]
+    }
+}
+
+impl Trash {
+    pub fn open(location: PathBuf) -> JeffResult<Self> {
+        let mut trash = Trash::load_path(location.join("index"))?;
+        trash.location = location;
+
+        Ok(trash)
+    }
+
+    /// The trash of the given vault.
+    pub fn for_vault(vault: &Vault) -> JeffResult<Self> {
+        Trash::open(vault.get_location().to_path_buf().join(".jeff/trash"))
+    }
+
+    /// The trash that removed vaults are kept in.
+    pub fn for_vaults() -> JeffResult<Self> {
+        Trash::open(application_data_path().join("trash"))
+    }
+
+    pub fn items(&self) -> &Vec<TrashItem> {
+        &self.items
+    }
+
+    /// Returns the most recently removed item with the given name, id, or
+    /// original path.
+    pub fn find(&self, name: &str) -> Option<&TrashItem> {
+        self.items.iter().rev().find(|item| item.matches(name))
+    }
+
+    /// Updates the index, keeping the location of the trash (which isn't
+    /// stored in the index).
+    fn modify<F: FnOnce(&mut Vec<TrashItem>)>(&mut self, change: F) -> JeffResult<()> {
+        let location = self.location.clone();
+        let result = self.update(|trash| change(&mut trash.items));
+        self.location = location;
+
+        result
+    }
+
+    /**
+     * Moves the file or folder at `path` into the trash.
+     * `original_path` is where it is restored to.
+     */
+    pub fn put(
+        &mut self,
+        item_type: ItemType,
+        path: &Path,
+        original_path: PathBuf,
+    ) -> JeffResult<TrashItem> {
+        let removed_at = Local::now();
+        let name = path
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().to_string());
+        let timestamp = removed_at.format("%Y%m%d%H%M%S");
+
+        let mut id = format!("{}-{}", timestamp, name);
+        let mut count = 1;
+        while self.location.join(&id).exists() {
+            id = format!("{}-{}-{}", timestamp, count, name);
+            count += 1;
+        }
+
+        move_path(path, &self.location.join(&id))?;
+
+        let item = TrashItem {
+            id,
+            item_type,
+            original_path,
+            removed_at,
+        };
+        let new_item = item.clone();
+        self.modify(|items| items.push(new_item))?;
+
+        Ok(item)
+    }
+
+    /**
+     * Moves the item found by [Trash::find] back to where it was removed
+     * from. Relative original paths are restored inside of `root`.
+     */
+    pub fn restore(&mut self, name: &str, root: &Path) -> JeffResult<TrashItem> {
+        let item = self
+            .find(name)
+            .cloned()
+            .ok_or_else(|| Error::TrashItemNotFound(name.to_owned()))?;
+
+        self.restore_to(&item, &root.join(&item.original_path))?;
+
+        Ok(item)
+    }
+
+    /// Moves an item out of the trash to `destination`.
+    pub fn restore_to(&mut self, item: &TrashItem, destination: &Path) -> JeffResult<()> {
+        if destination.exists() {
+            return Err(Error::ItemAlreadyExists(item.item_type, item.name()));
+        }
+        if let Some(parent) = destination.parent() {
+            create_dir_all(parent)?;
+        }
+
+        move_path(&self.item_path(item), destination)?;
+        self.modify(|items| items.retain(|other| other.id != item.id))
+    }
+
+    /// Absolute path to an item inside of the trash.
+    pub fn item_path(&self, item: &TrashItem) -> PathBuf {
+        self.location.join(&item.id)
+    }
+
+    /// Permanently deletes every item, or only the ones removed longer than
+    /// `older_than` ago. Returns the deleted items.
+    pub fn empty(&mut self, older_than: Option<Duration>) -> JeffResult<Vec<TrashItem>> {
+        let now = Local::now();
+        let expired: Vec<TrashItem> = self
+            .items
+            .iter()
+            .filter(|item| older_than.is_none_or(|age| now - item.removed_at >= age))
+            .cloned()
+            .collect();
+
+        for item in &expired {
+            let path = self.item_path(item);
+            if path.is_dir() {
+                remove_dir_all(&path)?;
+            } else if path.exists() {
+                remove_file(&path)?;
+            }
+        }
+
+        self.modify(|items| {
+            items.retain(|item| !expired.iter().any(|expired| expired.id == item.id))
+        })?;
+
+        Ok(expired)
+    }
+}
+
+/**
+ * Moves a file or folder. Vaults can be on another filesystem than the trash
+ * that they are moved to, where they can't be renamed, so they are copied
+ * and then removed instead.
+ */
+fn move_path(from: &Path, to: &Path) -> JeffResult<()> {
+    match rename(from, to) {
+        Err(error) if error.kind() == ErrorKind::CrossesDevices => copy_and_remove(from, to),
+        result => Ok(result?),
+    }
+}
+
+fn copy_and_remove(from: &Path, to: &Path) -> JeffResult<()> {
+    let existed = to.exists();
+    if let Err(error) = copy_all(from, to) {
+        // remove what was copied, leaving only the original
+        if !existed {
+            let _ = if to.is_dir() {
+                remove_dir_all(to)
+            } else {
+                remove_file(to)
+            };
+        }
+        return Err(error.into());
+    }
+
+    if from.is_dir() {
+        remove_dir_all(from)?;
+    } else {
+        remove_file(from)?;
+    }
+
+    Ok(())
+}
+
+/// Copies a file, or a folder and everything inside of it. Symbolic links
+/// are copied as links.
+fn copy_all(from: &Path, to: &Path) -> io::Result<()> {
+    let file_type = from.symlink_metadata()?.file_type();
+    if file_type.is_dir() {
+        create_dir(to)?;
+        for entry in read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else if file_type.is_symlink() {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(std::fs::read_link(from)?, to)?;
+        #[cfg(not(unix))]
+        copy(from, to)?;
+    } else {
+        copy(from, to)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn trash_tests() {
+    let root = std::env::temp_dir().join(format!("jeff_trash_{}", std::process::id()));
+    let note = root.join("folder/note.md");
+    create_dir_all(note.parent().unwrap()).unwrap();
+    std::fs::write(&note, "contents").unwrap();
+
+    let mut trash = Trash::open(root.join(".jeff/trash")).unwrap();
+    let item = trash
+        .put(ItemType::Note, &note, PathBuf::from("folder/note.md"))
+        .unwrap();
+    assert!(!note.exists());
+    assert_eq!("note", item.name());
+
+    // the index is stored with the trash
+    let trash_copy = Trash::open(root.join(".jeff/trash")).unwrap();
+    assert_eq!(1, trash_copy.items().len());
+
+    std::fs::write(&note, "new contents").unwrap();
+    assert!(trash.restore("note", &root).is_err());
+    std::fs::remove_file(&note).unwrap();
+
+    trash.restore("note", &root).unwrap();
+    assert_eq!("contents", std::fs::read_to_string(&note).unwrap());
+    assert!(trash.items().is_empty());
+    assert!(trash.restore("note", &root).is_err());
+
+    trash
+        .put(ItemType::Note, &note, PathBuf::from("note.md"))
+        .unwrap();
+    assert!(trash.empty(Some(Duration::days(30))).unwrap().is_empty());
+    assert_eq!(1, trash.empty(None).unwrap().len());
+    assert!(trash.items().is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn copy_and_remove_tests() {
+    let root = std::env::temp_dir().join(format!("jeff_trash_copy_{}", std::process::id()));
+    let vault = root.join("vault");
+    create_dir_all(vault.join(".jeff")).unwrap();
+    std::fs::write(vault.join(".jeff/data"), "schema_version = 2").unwrap();
+    std::fs::write(vault.join("note.md"), "contents").unwrap();
+
+    let moved = root.join("trash/vault");
+    create_dir_all(moved.parent().unwrap()).unwrap();
+    copy_and_remove(&vault, &moved).unwrap();
+    assert!(!vault.exists());
+    assert_eq!(
+        "contents",
+        std::fs::read_to_string(moved.join("note.md")).unwrap()
+    );
+    assert!(moved.join(".jeff/data").is_file());
+
+    // the original is kept when the copy fails
+    assert!(copy_and_remove(&moved, &root.join("missing/vault")).is_err());
+    assert!(moved.join("note.md").is_file());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -1,11 +1,22 @@
+use crate::archive;
 use crate::prelude::*;
-use anyhow::anyhow;
 use data::Data;
 use std::path::{Path, PathBuf};
 
+/// A registered vault.
+#[derive(Debug, Clone)]
+pub struct VaultInfo {
+    pub name: String,
+    /// absolute path to the vault
+    pub path: PathBuf,
+    pub current: bool,
+}
+
 #[derive(Debug)]
 pub struct Vaults {
     current: Option<Vault>,
+    /// the current vault, if its folder is gone
+    missing_current: Option<String>,
     data: Data,
 }
 
@@ -13,69 +24,83 @@ impl Vaults {
     pub fn load() -> JeffResult<Self> {
         let mut vaults = Vaults {
             current: None,
-            data: Data::load(),
+            missing_current: None,
+            data: Data::load()?,
         };
         vaults.load_current_vault()?;
         Ok(vaults)
     }
 
     pub fn get_vault_path(&self, name: &String) -> JeffResult<PathBuf> {
-        if let Some(vault_parent_dir) = self.data.get_vault_location(name) {
-            Ok(get_absolute_path(vault_parent_dir, name))
-        } else {
-            Err(anyhow!("Invalid vault path"))
-        }
+        self.data
+            .get_vault_path(name)
+            .cloned()
+            .ok_or_else(|| Error::VaultNotFound(name.to_owned()))
     }
 
     pub fn get_vault(&self, name: &String) -> JeffResult<Vault> {
         let vault_abs_path = self.get_vault_path(name)?;
+        if !vault_abs_path.is_dir() {
+            return Err(Error::VaultPathMissing(name.to_owned(), vault_abs_path));
+        }
         let vault = Vault::load(vault_abs_path)?;
         Ok(vault)
     }
 
-    fn load_current_vault(&mut self) -> JeffResult<()> {
-        self.current = if let Some(current_vault_name) = self.data.get_current_vault() {
-            let vault_absolute_path = self.get_vault_path(current_vault_name)?;
-            let current_vault = Vault::load(vault_absolute_path)?;
-            Some(current_vault)
-        } else {
-            None
-        };
+    /// Loads the current vault. If its folder is gone, e.g. because it is on
+    /// a drive that isn't plugged in, there is no current vault for now.
+    pub fn load_current_vault(&mut self) -> JeffResult<()> {
+        self.current = None;
+        self.missing_current = None;
+
+        if let Some(current_vault_name) = self.data.get_current_vault().cloned() {
+            match self.get_vault(&current_vault_name) {
+                Ok(current_vault) => self.current = Some(current_vault),
+                Err(Error::VaultPathMissing(..)) => self.missing_current = Some(current_vault_name),
+                Err(error) => return Err(error),
+            }
+        }
 
         Ok(())
     }
 
-    pub fn list_vaults(&self, show_loc: bool) {
-        for vault_name in self.data.get_vaults().keys() {
-            if self.is_current_vault(vault_name) {
-                print!("👉 \x1b[0;34m{}\x1b[0m", vault_name)
-            } else {
-                print!("   {}", vault_name)
-            }
+    /// Returns the error for the current vault, if its folder is gone.
+    pub fn missing_current(&self) -> Option<Error> {
+        let name = self.missing_current.as_ref()?;
+        let path = self.get_vault_path(name).ok()?;
 
-            if show_loc {
-                println!(
-                    " \t {}",
-                    self.data.get_vault_location(vault_name).unwrap().display()
-                )
-            } else {
-                println!();
-            }
-        }
+        Some(Error::VaultPathMissing(name.to_owned(), path))
     }
 
-    pub fn show_vault_location(&self, vault_name: String) {
-        if let Some(vault_location) = self.data.get_vault_location(vault_name.as_str()) {
-            if self.is_current_vault(&vault_name) {
-                println!(
-                    "👉 \x1b[0;34m{}\x1b[0m \t {}",
-                    vault_name,
-                    vault_location.display()
-                );
-            } else {
-                println!("{} \t {}", vault_name, vault_location.display());
-            }
-        }
+    pub fn vault_names(&self) -> Vec<String> {
+        self.data.get_vaults().keys().cloned().collect()
+    }
+
+    /// Returns every registered vault, sorted by name.
+    pub fn list_vaults(&self) -> Vec<VaultInfo> {
+        let mut vaults: Vec<VaultInfo> = self
+            .data
+            .get_vaults()
+            .iter()
+            .map(|(name, path)| VaultInfo {
+                name: name.to_owned(),
+                path: path.to_owned(),
+                current: self.is_current_vault(name),
+            })
+            .collect();
+        vaults.sort_by(|a, b| a.name.cmp(&b.name));
+
+        vaults
+    }
+
+    /// Returns the name of the vault at `path`.
+    pub fn vault_name(&self, path: &Path) -> Option<String> {
+        self.data.vault_name(path).cloned()
+    }
+
+    /// Name of the current vault.
+    pub fn current_name(&self) -> Option<String> {
+        self.data.get_current_vault().cloned()
     }
 
     fn is_current_vault(&self, vault_name: &String) -> bool {
@@ -86,7 +111,7 @@ impl Vaults {
 
     pub fn ref_current(&self) -> JeffResult<&Vault> {
         if self.current.is_none() {
-            return Err(anyhow!("{}", Error::NotInsideVault));
+            return Err(Error::NotInsideVault);
         }
 
         Ok(self.current.as_ref().unwrap())
@@ -94,7 +119,7 @@ impl Vaults {
 
     pub fn mut_current(&mut self) -> JeffResult<&mut Vault> {
         if self.current.is_none() {
-            return Err(anyhow!("{}", Error::NotInsideVault));
+            return Err(Error::NotInsideVault);
         }
 
         Ok(self.current.as_mut().unwrap())
@@ -102,53 +127,210 @@ impl Vaults {
 
     pub fn create_vault(&mut self, name: &str, location: &Path) -> JeffResult<()> {
         if self.data.vault_exists(name) {
-            return Err(anyhow!(Error::VaultAlreadyExists(name.to_owned())));
+            return Err(Error::VaultAlreadyExists(name.to_owned()));
         }
 
         let location = process_path(location);
         let absolute_path = Vault::generate_abs_path(&location.to_path_buf(), &name.to_string());
+        self.check_not_nested(&absolute_path, None)?;
+
+        Vault::create(absolute_path.clone())?;
+
+        self.data.add_vault(name.to_owned(), absolute_path)?;
+
+        Ok(())
+    }
+
+    /**
+     * Registers an existing folder (e.g. an Obsidian vault) as a vault,
+     * without moving or copying it. Hidden folders inside of it, like
+     * `.obsidian`, are ignored.
+     */
+    pub fn import_vault(&mut self, name: &str, path: &Path) -> JeffResult<()> {
+        if self.data.vault_exists(name) {
+            return Err(Error::VaultAlreadyExists(name.to_owned()));
+        }
+
+        let path = process_path(&std::path::absolute(path)?);
+        if !path.is_dir() {
+            return Err(Error::InvalidPath(path));
+        }
+        self.check_not_nested(&path, None)?;
+
+        Vault::import(path.clone())?;
+        self.data.add_vault(name.to_owned(), path)?;
+
+        Ok(())
+    }
+
+    /// Points a vault to the folder it was moved to outside of jeff, without
+    /// moving any files.
+    pub fn relink_vault(&mut self, name: &str, new_path: &Path) -> JeffResult<PathBuf> {
+        if !self.data.vault_exists(name) {
+            return Err(Error::VaultNotFound(name.to_owned()));
+        }
+
+        let path = process_path(&std::path::absolute(new_path)?);
+        if !path.is_dir() {
+            return Err(Error::InvalidPath(path));
+        }
+        self.check_not_nested(&path, Some(name))?;
+
+        Vault::import(path.clone())?;
+        self.data.set_vault_path(name, path.clone())?;
+
+        if self.is_current_vault(&name.to_string()) {
+            self.load_current_vault()?;
+        }
+
+        Ok(path)
+    }
 
-        Vault::create(absolute_path)?;
+    /// Writes a vault, including its `.jeff` folder, to a `.tar.gz` or
+    /// `.zip` archive. Returns the absolute path of the archive.
+    pub fn archive_vault(&self, name: &str, archive: &Path) -> JeffResult<PathBuf> {
+        let vault_path = self
+            .get_vault(&name.to_string())?
+            .get_location()
+            .to_path_buf();
+        let archive = process_path(&std::path::absolute(archive)?);
+        if archive.starts_with(&vault_path) {
+            return Err(Error::OutputInsideVault(archive));
+        }
 
-        self.data.add_vault(name.to_owned(), location);
+        archive::create(&vault_path, &archive)?;
+
+        Ok(archive)
+    }
+
+    /**
+     * Extracts an archive made by [Vaults::archive_vault] to `location` and
+     * registers it as a vault. The name defaults to the name of the archived
+     * folder, and the location to the working directory. Returns the name
+     * and path of the vault.
+     */
+    pub fn restore_vault_archive(
+        &mut self,
+        archive: &Path,
+        name: Option<&str>,
+        location: Option<&Path>,
+    ) -> JeffResult<(String, PathBuf)> {
+        // checks that everything is inside of one folder, which is left out
+        // when the archive is extracted
+        let root_name = archive::root_name(archive)?;
+        let name = name.map_or(root_name, str::to_owned);
+        if self.data.vault_exists(&name) {
+            return Err(Error::VaultAlreadyExists(name));
+        }
+
+        let location = match location {
+            Some(location) => location.to_path_buf(),
+            None => std::env::current_dir()?,
+        };
+        let path = process_path(&std::path::absolute(location.join(&name))?);
+        if path.exists() {
+            return Err(Error::VaultAlreadyExists(name));
+        }
+        self.check_not_nested(&path, None)?;
+
+        if let Err(error) = archive::extract(archive, &path) {
+            let _ = std::fs::remove_dir_all(&path);
+            return Err(error);
+        }
+        // the vault's data still has the path it was archived from
+        Vault::import(path.clone())?;
+        self.data.add_vault(name.clone(), path.clone())?;
+
+        Ok((name, path))
+    }
+
+    /// Makes sure that a vault at `path` would neither be inside of another
+    /// vault nor contain one. `name` is the vault being moved, if any.
+    fn check_not_nested(&self, path: &Path, name: Option<&str>) -> JeffResult<()> {
+        for (other_name, other_path) in self.data.get_vaults() {
+            if Some(other_name.as_str()) == name {
+                continue;
+            }
+            if path.starts_with(other_path) || other_path.starts_with(path) {
+                return Err(Error::NestedVault(other_name.to_owned()));
+            }
+        }
 
         Ok(())
     }
 
-    pub fn remove_vault(&mut self, name: &str) -> JeffResult<()> {
+    /// Moves a vault to the trash. Returns the path of the vault inside of
+    /// the trash.
+    pub fn remove_vault(&mut self, name: &str) -> JeffResult<PathBuf> {
         let maybe_vault = self.get_vault(&name.to_string());
         if maybe_vault.is_err() {
-            return Err(anyhow!(Error::VaultNotFound(name.to_owned())));
+            return Err(Error::VaultNotFound(name.to_owned()));
         }
 
         let vault_to_remove = maybe_vault.unwrap();
 
-        self.data.remove_vault(name);
-        vault_to_remove.delete()?;
+        let vault_path = vault_to_remove.get_location().to_path_buf();
+        let mut trash = Trash::for_vaults()?;
+        let item = trash.put(ItemType::Vault, &vault_path, vault_path.clone())?;
+        self.data.remove_vault(name)?;
+
+        if self.data.get_current_vault().map(String::as_str) == Some(name) {
+            self.data.set_current_vault(None)?;
+        }
+
+        Ok(trash.item_path(&item))
+    }
+
+    /// Forgets a vault without touching its folder, e.g. after the folder
+    /// was deleted outside of jeff.
+    pub fn unregister_vault(&mut self, name: &str) -> JeffResult<()> {
+        if !self.data.vault_exists(name) {
+            return Err(Error::VaultNotFound(name.to_owned()));
+        }
 
-        if self.data.get_current_vault() == Some(&vault_to_remove.get_name()) {
-            self.data.set_current_vault(None);
+        self.data.remove_vault(name)?;
+        if self.data.get_current_vault().map(String::as_str) == Some(name) {
+            self.data.set_current_vault(None)?;
+            self.current = None;
         }
 
         Ok(())
     }
 
+    /// Restores a removed vault from the trash and registers it again.
+    pub fn restore_vault(&mut self, name: &str) -> JeffResult<TrashItem> {
+        let mut trash = Trash::for_vaults()?;
+        let vault_name = trash
+            .find(name)
+            .map(|item| item.name())
+            .ok_or_else(|| Error::TrashItemNotFound(name.to_owned()))?;
+        if self.data.vault_exists(&vault_name) {
+            return Err(Error::VaultAlreadyExists(vault_name));
+        }
+
+        let item = trash.restore(name, Path::new(""))?;
+        self.data
+            .add_vault(item.name(), item.original_path.clone())?;
+
+        Ok(item)
+    }
+
     pub fn rename_vault(&mut self, name: &str, new_name: &str) -> JeffResult<()> {
         if self.data.vault_exists(new_name) {
-            return Err(anyhow!(Error::VaultAlreadyExists(new_name.to_owned())));
+            return Err(Error::VaultAlreadyExists(new_name.to_owned()));
         } else if !self.data.vault_exists(name) {
-            return Err(anyhow!(Error::VaultNotFound(name.to_owned())));
+            return Err(Error::VaultNotFound(name.to_owned()));
         }
 
-        let vault_parent_dir = self.data.get_vault_location(name).unwrap();
-        let vault_absolute_path = Vault::generate_abs_path(&vault_parent_dir, &name.to_string());
-        let mut vault = Vault::load(vault_absolute_path)?;
+        let mut vault = self.get_vault(&name.to_string())?;
 
         vault.rename(new_name.to_owned())?;
-        self.data.rename_vault(name, new_name.to_owned());
+        let new_path = vault.get_location().to_path_buf();
+        self.data
+            .rename_vault(name, new_name.to_owned(), new_path)?;
 
         if self.data.get_current_vault() == Some(&name.to_string()) {
-            self.data.set_current_vault(Some(new_name.to_string()));
+            self.data.set_current_vault(Some(new_name.to_string()))?;
         }
 
         Ok(())
@@ -156,39 +338,37 @@ impl Vaults {
 
     pub fn move_vault(&mut self, name: &str, new_location: &Path) -> JeffResult<()> {
         if !self.data.vault_exists(name) {
-            return Err(anyhow!(Error::VaultNotFound(name.to_owned())));
+            return Err(Error::VaultNotFound(name.to_owned()));
         }
 
-        let vault_parent_dir = self.data.get_vault_location(name).unwrap();
-        let vault_absolute_path = Vault::generate_abs_path(&vault_parent_dir, &name.to_string());
-        let mut vault = Vault::load(vault_absolute_path)?;
+        let mut vault = self.get_vault(&name.to_string())?;
 
         let new_absolute_path = process_path(&join_paths(vec![
             new_location.to_str().unwrap().to_string(),
             vault.get_name(),
         ]));
+        self.check_not_nested(&new_absolute_path, Some(name))?;
 
         vault.relocate(new_absolute_path.to_owned())?;
 
-        self.data
-            .set_vault_location(name, new_location.to_path_buf());
+        self.data.set_vault_path(name, new_absolute_path)?;
 
         Ok(())
     }
 
     pub fn enter_vault(&mut self, name: &str) -> JeffResult<()> {
         if !self.data.vault_exists(name) {
-            return Err(anyhow!(Error::VaultNotFound(name.to_owned())));
+            return Err(Error::VaultNotFound(name.to_owned()));
         }
 
         if let Some(current_vault_name) = self.data.get_current_vault() {
             if name == current_vault_name {
-                return Err(anyhow!(Error::AlreadyInVault(name.to_owned())));
+                return Err(Error::AlreadyInVault(name.to_owned()));
             }
         }
 
         self.current = Some(self.get_vault(&name.to_owned())?);
-        self.data.set_current_vault(Some(name.to_owned()));
+        self.data.set_current_vault(Some(name.to_owned()))?;
 
         Ok(())
     }
@@ -89,12 +89,12 @@ pub fn execute_commands(commands: Vec<Test>) {
     }
 }
 
-pub fn display_item_color_select<T: Item + Colored>() -> String {
+pub fn display_item_color_select<T: Item + Colored>() -> JeffResult<String> {
     let prompt = format!("Select a color.");
     display_color_select(prompt)
 }
 
-pub fn display_color_select(prompt: String) -> String {
+pub fn display_color_select(prompt: String) -> JeffResult<String> {
     let colors = [
         "black",
         "red",
@@ -117,8 +117,7 @@ pub fn display_color_select(prompt: String) -> String {
     let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
         .items(&colors)
         .default(0)
-        .interact()
-        .unwrap();
+        .interact()?;
 
-    colors[selection].to_string()
+    Ok(colors[selection].to_string())
 }
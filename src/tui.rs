@@ -0,0 +1,569 @@
+/**
+ * Full-screen terminal ui for browsing the current vault.
+ *
+ * Every action is performed through the same [App] operations as the
+ * command line, with the active folder set to the folder of the selected item.
+ */
+use std::io::stdout;
+use std::path::PathBuf;
+
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use ratatui::{
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    app::{App, Scope},
+    prelude::*,
+};
+
+/// A note or folder in the vault tree.
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    item_type: ItemType,
+    /// path of the parent folder, relative to the vault ("" for the root)
+    folder: String,
+    depth: usize,
+    location: PathBuf,
+}
+
+impl Entry {
+    /// Path of the entry, relative to the vault.
+    fn path(&self) -> String {
+        path_to_string(join_paths(vec![&self.folder, &self.name]))
+    }
+
+    /// Folder that new items are created in when this entry is selected.
+    fn target_folder(&self) -> String {
+        match self.item_type {
+            ItemType::Fd => self.path(),
+            _ => self.folder.to_owned(),
+        }
+    }
+}
+
+fn load_entries<C: Collection + ?Sized>(
+    collection: &C,
+    folder: &str,
+    depth: usize,
+    entries: &mut Vec<Entry>,
+) {
+    for child in collection.folders_sorted() {
+        let entry = Entry {
+            name: child.get_name(),
+            item_type: ItemType::Fd,
+            folder: folder.to_owned(),
+            depth,
+            location: child.get_location().to_path_buf(),
+        };
+        let path = entry.path();
+        entries.push(entry);
+        load_entries(&child, &path, depth + 1, entries);
+    }
+
+    for note in collection.notes_sorted() {
+        entries.push(Entry {
+            name: note.get_name(),
+            item_type: ItemType::Nt,
+            folder: folder.to_owned(),
+            depth,
+            location: note.get_location().to_path_buf(),
+        });
+    }
+}
+
+/// Returns the indices of the entries that match the filter, best match first.
+fn filter_entries(entries: &[Entry], filter: &str) -> Vec<usize> {
+    if filter.is_empty() {
+        return (0..entries.len()).collect();
+    }
+
+    let matcher = SkimMatcherV2::default();
+    let mut matches: Vec<(i64, usize)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            matcher
+                .fuzzy_match(&entry.path(), filter)
+                .map(|score| (score, i))
+        })
+        .collect();
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    matches.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Path from `folder` back up to the root of the vault.
+fn path_to_root(folder: &str) -> PathBuf {
+    let depth = PathBuf::from(folder).iter().count();
+
+    join_paths(vec![".."; depth])
+}
+
+/// Removes the ansi color codes from a message.
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        if char == '\x1b' {
+            // skip to the end of the escape sequence
+            for char in chars.by_ref() {
+                if char.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(char);
+        }
+    }
+
+    stripped
+}
+
+fn tui_color(color: colored::Color) -> Color {
+    match color {
+        colored::Color::Black => Color::Black,
+        colored::Color::Red => Color::Red,
+        colored::Color::Green => Color::Green,
+        colored::Color::Yellow => Color::Yellow,
+        colored::Color::Blue => Color::Blue,
+        colored::Color::Magenta => Color::Magenta,
+        colored::Color::Cyan => Color::Cyan,
+        colored::Color::White => Color::Gray,
+        colored::Color::BrightBlack => Color::DarkGray,
+        colored::Color::BrightRed => Color::LightRed,
+        colored::Color::BrightGreen => Color::LightGreen,
+        colored::Color::BrightYellow => Color::LightYellow,
+        colored::Color::BrightBlue => Color::LightBlue,
+        colored::Color::BrightMagenta => Color::LightMagenta,
+        colored::Color::BrightCyan => Color::LightCyan,
+        colored::Color::BrightWhite => Color::White,
+        colored::Color::TrueColor { r, g, b } => Color::Rgb(r, g, b),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Action {
+    CreateNote,
+    CreateFolder,
+    Rename,
+    Move,
+}
+
+impl Action {
+    fn prompt(&self) -> &str {
+        match self {
+            Action::CreateNote => "new note",
+            Action::CreateFolder => "new folder",
+            Action::Rename => "rename to",
+            Action::Move => "move to (path from vault root)",
+        }
+    }
+}
+
+enum Mode {
+    Browse,
+    Filter,
+    Input(Action, String),
+    Confirm,
+}
+
+pub struct Tui<'a> {
+    app: &'a mut App,
+    entries: Vec<Entry>,
+    /// indices of the entries that match the filter
+    visible: Vec<usize>,
+    list_state: ListState,
+    filter: String,
+    mode: Mode,
+    status: String,
+    /// active folder before the ui was opened, restored on exit
+    initial_folder: Option<String>,
+}
+
+impl<'a> Tui<'a> {
+    pub fn new(app: &'a mut App) -> JeffResult<Self> {
+        let initial_folder = app.current_vault()?.get_active_folder_path();
+
+        let mut tui = Tui {
+            app,
+            entries: vec![],
+            visible: vec![],
+            list_state: ListState::default(),
+            filter: String::new(),
+            mode: Mode::Browse,
+            status: String::new(),
+            initial_folder,
+        };
+        tui.load()?;
+
+        Ok(tui)
+    }
+
+    /// Reloads the vault tree, keeping the selection on the same path if it
+    /// still exists.
+    fn load(&mut self) -> JeffResult<()> {
+        let selected_path = self.selected().map(|entry| entry.path());
+
+        self.app.reload()?;
+        self.entries = vec![];
+        load_entries(self.app.current_vault()?, "", 0, &mut self.entries);
+        self.apply_filter();
+
+        if let Some(path) = selected_path {
+            if let Some(position) = self
+                .visible
+                .iter()
+                .position(|i| self.entries[*i].path() == path)
+            {
+                self.list_state.select(Some(position));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_filter(&mut self) {
+        self.visible = filter_entries(&self.entries, &self.filter);
+
+        let selected = self.list_state.selected().unwrap_or(0);
+        if self.visible.is_empty() {
+            self.list_state.select(None);
+        } else {
+            self.list_state
+                .select(Some(selected.min(self.visible.len() - 1)));
+        }
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        let position = self.list_state.selected()?;
+
+        self.visible.get(position).map(|i| &self.entries[*i])
+    }
+
+    pub fn run(&mut self) -> JeffResult<()> {
+        let mut terminal = ratatui::try_init()?;
+        let result = self.event_loop(&mut terminal);
+        ratatui::restore();
+
+        self.app.set_active_folder(self.initial_folder.to_owned())?;
+
+        result
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> JeffResult<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key, terminal)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Handles a key press. Returns false once the ui should close.
+    fn handle_key(&mut self, key: KeyEvent, terminal: &mut DefaultTerminal) -> JeffResult<bool> {
+        // Messages are only shown until the next key press.
+        self.status.clear();
+
+        match &mut self.mode {
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Char('g') | KeyCode::Home => self.list_state.select_first(),
+                KeyCode::Char('G') | KeyCode::End => {
+                    self.list_state.select(self.visible.len().checked_sub(1))
+                }
+                KeyCode::Char('/') => self.mode = Mode::Filter,
+                KeyCode::Enter | KeyCode::Char('o') => self.open(terminal)?,
+                KeyCode::Char('n') => self.start_input(Action::CreateNote),
+                KeyCode::Char('f') => self.start_input(Action::CreateFolder),
+                KeyCode::Char('r') => self.start_input(Action::Rename),
+                KeyCode::Char('m') => self.start_input(Action::Move),
+                KeyCode::Char('d') if self.selected().is_some() => self.mode = Mode::Confirm,
+                _ => (),
+            },
+            Mode::Filter => match key.code {
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Browse;
+                    self.apply_filter();
+                }
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Down => self.move_selection(1),
+                KeyCode::Up => self.move_selection(-1),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.apply_filter();
+                }
+                KeyCode::Char(char) => {
+                    self.filter.push(char);
+                    self.list_state.select_first();
+                    self.apply_filter();
+                }
+                _ => (),
+            },
+            Mode::Input(action, input) => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Enter => {
+                    let (action, input) = (*action, input.trim().to_owned());
+                    self.mode = Mode::Browse;
+                    self.perform(action, input)?;
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(char) => input.push(char),
+                _ => (),
+            },
+            Mode::Confirm => {
+                self.mode = Mode::Browse;
+                if let KeyCode::Char('y') = key.code {
+                    self.delete()?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+
+        let selected = self.list_state.selected().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        self.list_state
+            .select(Some((selected + offset).clamp(0, last) as usize));
+    }
+
+    fn start_input(&mut self, action: Action) {
+        let input = match (action, self.selected()) {
+            (Action::Rename, Some(entry)) => entry.name.to_owned(),
+            (Action::Move, Some(entry)) => entry.folder.to_owned(),
+            (Action::Rename | Action::Move, None) => return,
+            _ => String::new(),
+        };
+
+        self.mode = Mode::Input(action, input);
+    }
+
+    /// Runs an [App] operation from the given folder, then reloads the tree.
+    fn run_from_folder<F>(&mut self, folder: String, operation: F) -> JeffResult<()>
+    where
+        F: FnOnce(&mut App) -> JeffResult<Message>,
+    {
+        self.app.set_active_folder(Some(folder))?;
+
+        let result = operation(self.app);
+        let committed = match result {
+            Ok(_) => self.app.finish_command("jf tui".to_owned()),
+            Err(_) => Ok(()),
+        };
+
+        self.status = match (result, committed) {
+            (Ok(_), Err(Error::GitFailed(error))) => strip_ansi(&format!(
+                "warning: changes weren't committed to git: {}",
+                error
+            )),
+            (_, Err(error)) => return Err(error),
+            (Ok(Message::Empty), _) => String::new(),
+            (Ok(message), _) => strip_ansi(&message.to_string()),
+            (Err(error), _) => strip_ansi(&format!("error: {}", error)),
+        };
+
+        self.load()
+    }
+
+    fn perform(&mut self, action: Action, input: String) -> JeffResult<()> {
+        let entry = self.selected().cloned();
+
+        match (action, entry) {
+            (Action::CreateNote, entry) => {
+                let folder = entry.map(|entry| entry.target_folder()).unwrap_or_default();
+                self.run_from_folder(folder, |app| app.create_note(&input, false, &None, &None))
+            }
+            (Action::CreateFolder, entry) => {
+                let folder = entry.map(|entry| entry.target_folder()).unwrap_or_default();
+                self.run_from_folder(folder, |app| app.create_folder(&input))
+            }
+            (Action::Rename, Some(entry)) => self.run_from_folder(entry.folder, |app| {
+                app.rename_item_in(Scope::ActiveFolder, entry.item_type, &entry.name, &input)
+            }),
+            (Action::Move, Some(entry)) => {
+                // Notes are moved relative to their folder, folders relative
+                // to the root of the vault.
+                let new_location = match entry.item_type {
+                    ItemType::Nt => join_paths(vec![path_to_root(&entry.folder), input.into()]),
+                    _ => PathBuf::from(input),
+                };
+
+                self.run_from_folder(entry.folder, |app| {
+                    app.move_item_in(
+                        Scope::ActiveFolder,
+                        entry.item_type,
+                        &entry.name,
+                        &new_location,
+                    )
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn delete(&mut self) -> JeffResult<()> {
+        if let Some(entry) = self.selected().cloned() {
+            self.run_from_folder(entry.folder, |app| {
+                app.delete_item(Scope::ActiveFolder, entry.item_type, &entry.name)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Opens the selected note in the editor, leaving the ui while it runs.
+    fn open(&mut self, terminal: &mut DefaultTerminal) -> JeffResult<()> {
+        let entry = match self.selected() {
+            Some(entry) if entry.item_type == ItemType::Nt => entry.clone(),
+            _ => return Ok(()),
+        };
+
+        disable_raw_mode()?;
+        execute!(stdout(), LeaveAlternateScreen)?;
+
+        let result =
+            self.run_from_folder(entry.folder.to_owned(), |app| app.open_note(&entry.name));
+
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen)?;
+        terminal.clear()?;
+
+        result
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [tree_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main_area);
+
+        let (vault_name, vault_color, folder_color, note_color) = {
+            let config = CONFIG.lock().unwrap();
+            (
+                self.app
+                    .location()
+                    .map(|(vault_name, _)| vault_name)
+                    .unwrap_or_default(),
+                tui_color(config.get_vault_color()),
+                tui_color(config.get_folder_color()),
+                tui_color(config.get_note_color()),
+            )
+        };
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|i| {
+                let entry = &self.entries[*i];
+                let color = match entry.item_type {
+                    ItemType::Fd => folder_color,
+                    _ => note_color,
+                };
+                // Filtered results are shown as paths instead of a tree.
+                let text = if self.filter.is_empty() {
+                    format!("{}{}", "  ".repeat(entry.depth), entry.name)
+                } else {
+                    entry.path()
+                };
+
+                ListItem::new(Span::styled(text, Style::default().fg(color)))
+            })
+            .collect();
+
+        let tree = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(Span::styled(vault_name, Style::default().fg(vault_color))),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(tree, tree_area, &mut self.list_state);
+
+        let (title, preview) = match self.selected() {
+            Some(entry) if entry.item_type == ItemType::Fd => {
+                let children: Vec<String> = self
+                    .entries
+                    .iter()
+                    .filter(|child| child.folder == entry.path())
+                    .map(|child| child.name.to_owned())
+                    .collect();
+                (entry.path(), children.join("\n"))
+            }
+            Some(entry) => (
+                entry.path(),
+                std::fs::read_to_string(&entry.location).unwrap_or_default(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let preview = Paragraph::new(preview)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(preview, preview_area);
+
+        let status = match &self.mode {
+            Mode::Browse if !self.status.is_empty() => Line::from(self.status.to_owned()),
+            Mode::Browse => Line::from(
+                "enter open · n note · f folder · r rename · m move · d delete · / filter · q quit",
+            ),
+            Mode::Filter => Line::from(format!("/{}", self.filter)),
+            Mode::Input(action, input) => Line::from(format!("{}: {}", action.prompt(), input)),
+            Mode::Confirm => Line::from(format!(
+                "remove {}? (y/n)",
+                self.selected()
+                    .map(|entry| entry.path())
+                    .unwrap_or_default()
+            )),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+}
+
+#[test]
+fn tui_helper_tests() {
+    let entry = |name: &str, folder: &str| Entry {
+        name: name.to_string(),
+        item_type: ItemType::Nt,
+        folder: folder.to_string(),
+        depth: 0,
+        location: PathBuf::new(),
+    };
+    let entries = vec![
+        entry("ideas", ""),
+        entry("meeting", "work"),
+        entry("idle", "work"),
+    ];
+
+    assert_eq!(vec![0, 1, 2], filter_entries(&entries, ""));
+    assert_eq!(vec![1], filter_entries(&entries, "wmeet"));
+    assert!(filter_entries(&entries, "zzz").is_empty());
+
+    assert_eq!(PathBuf::from(""), path_to_root(""));
+    assert_eq!(PathBuf::from("../.."), path_to_root("work/notes"));
+    assert_eq!("note created", strip_ansi("note \x1b[0;34mcreated\x1b[0m"));
+}
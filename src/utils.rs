@@ -1,3 +1,6 @@
+#[cfg(not(test))]
+use std::io::IsTerminal;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
 use chrono;
@@ -7,8 +10,8 @@ use directories::ProjectDirs;
 
 use crate::prelude::*;
 
-#[allow(unused)]
-fn valid_name(name: &str) -> bool {
+/// Whether `name` can be used as the name of a file or folder.
+pub fn valid_name(name: &str) -> bool {
     name.chars().all(|char| !r#"\/?%*:|"<>"#.contains(char))
 }
 
@@ -42,6 +45,24 @@ pub fn process_path(path: &Path) -> PathBuf {
     processed_path
 }
 
+/// Returns the path to `path` from the folder `from`, e.g. `../b/c.png`.
+/// Both paths must be absolute.
+pub fn relative_path(from: &Path, path: &Path) -> PathBuf {
+    let common = from
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in from.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(path.components().skip(common));
+
+    relative
+}
+
 fn generate_date_string() -> String {
     let local_timestamp = chrono::offset::Local::now();
     let local_date = local_timestamp.date_naive();
@@ -53,6 +74,49 @@ pub fn daily_note_name() -> String {
     generate_date_string()
 }
 
+/// Parses an age such as `30d`, `12h`, `45m` or `2w`.
+pub fn parse_age(age: &str) -> Result<chrono::Duration, String> {
+    let (amount, unit) = age.split_at(age.len() - age.trim_start_matches(char::is_numeric).len());
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid age {}, expected e.g. 30d", age))?;
+
+    match unit {
+        "m" => Ok(chrono::Duration::minutes(amount)),
+        "h" => Ok(chrono::Duration::hours(amount)),
+        "d" => Ok(chrono::Duration::days(amount)),
+        "w" => Ok(chrono::Duration::weeks(amount)),
+        _ => Err(format!("invalid unit in {}, expected m, h, d, or w", age)),
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date, or an age like `2d` counted back from now.
+pub fn parse_since(since: &str) -> Result<chrono::DateTime<chrono::Local>, String> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|time| time.and_local_timezone(chrono::Local).earliest())
+            .ok_or_else(|| format!("invalid date {}", since));
+    }
+
+    parse_age(since)
+        .map(|age| chrono::Local::now() - age)
+        .map_err(|_| format!("invalid date {}, expected e.g. 2024-01-31 or 2d", since))
+}
+
+/// Returns the local time formatted as `HH:MM`.
+pub fn generate_time_string() -> String {
+    chrono::offset::Local::now().format("%H:%M").to_string()
+}
+
+/// Reads all of stdin into a string.
+pub fn read_stdin() -> JeffResult<String> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    Ok(input)
+}
+
 pub fn path_to_string(path: PathBuf) -> String {
     path.to_str().unwrap().to_string()
 }
@@ -103,14 +167,64 @@ pub fn item_with_name<'a, I: Item>(items: &'a Vec<I>, name: &String) -> Option<&
 /// Returns the outcome, "true" if the action was confirmed
 /// and "false" otherwise.
 #[cfg(not(test))]
-pub fn confirmation_prompt(prompt: String) -> bool {
-    Confirm::with_theme(&ColorfulTheme::default())
+pub fn confirmation_prompt(prompt: String) -> JeffResult<bool> {
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
-        .interact()
-        .unwrap()
+        .interact()?)
 }
 
 #[cfg(test)]
-pub fn confirmation_prompt(_prompt: String) -> bool {
+pub fn confirmation_prompt(_prompt: String) -> JeffResult<bool> {
+    Ok(true)
+}
+
+#[cfg(not(test))]
+pub fn stdin_is_terminal() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+#[cfg(test)]
+pub fn stdin_is_terminal() -> bool {
     true
 }
+
+/// Decides how prompts are answered: by the user, or, when `--yes` or
+/// `--no-input` is given or stdin is not a terminal, without any input.
+#[derive(Debug, Clone, Copy)]
+pub struct Prompts {
+    /// confirm every prompt without asking
+    assume_yes: bool,
+    /// whether the user can be asked for input
+    interactive: bool,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Prompts::new(false, false)
+    }
+}
+
+impl Prompts {
+    pub fn new(assume_yes: bool, no_input: bool) -> Self {
+        Prompts {
+            assume_yes,
+            interactive: !no_input && stdin_is_terminal(),
+        }
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// Asks the user to confirm an action. Without input, the action is only
+    /// confirmed if `--yes` was given.
+    pub fn confirm(&self, prompt: String) -> JeffResult<bool> {
+        if self.assume_yes {
+            Ok(true)
+        } else if self.interactive {
+            confirmation_prompt(prompt)
+        } else {
+            Err(Error::InputRequired(prompt))
+        }
+    }
+}